@@ -1,24 +1,45 @@
+extern crate flate2;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 extern crate zip;
 
+use self::flate2::read::GzDecoder;
+#[cfg(feature = "rayon")]
+use self::rayon::prelude::*;
 use self::zip::ZipArchive;
 use self::zip::result::ZipError;
 
-use std::collections::HashMap;
+#[cfg(feature = "rayon")]
+use std::cmp::max;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+#[cfg(feature = "rayon")]
 use std::collections::hash_map::Entry;
 use std::error::Error as StdError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Error as IOError};
+use std::io::{self, BufRead, BufReader, Error as IOError};
+#[cfg(test)]
+use std::env;
+#[cfg(test)]
+use std::fs;
+#[cfg(test)]
+use std::io::{Cursor, Write};
 use std::num::ParseIntError;
+#[cfg(test)]
+use std::process;
 use std::result::Result;
 use std::string::String;
+use std::time::{Duration, Instant};
 use std::vec::Vec;
 
 #[derive(Debug)]
 pub enum Error {
 	IoError(IOError),
 	ParseError(ParseIntError),
-	ZipError(ZipError)
+	ZipError(ZipError),
+	FormatError { message: String }
 }
 
 impl From<ParseIntError> for Error {
@@ -44,62 +65,502 @@ impl Display for Error {
 		match *self {
 			Error::IoError(ref err) => write!(f, "IO Error: {}", err),
 			Error::ParseError(ref err) => write!(f, "Parse Error: {}", err),
-			Error::ZipError(ref err) => write!(f, "Zip Error: {}", err)
+			Error::ZipError(ref err) => write!(f, "Zip Error: {}", err),
+			Error::FormatError { ref message } => write!(f, "Format Error: {}", message)
 		}
 	}
 }
 
 impl StdError for Error {
-	fn description(&self) -> &str {
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
 		match *self {
-			Error::IoError(ref err) => err.description(),
-			Error::ParseError(ref err) => err.description(),
-			Error::ZipError(ref err) => err.description()
+			Error::IoError(ref err) => Some(err),
+			Error::ParseError(ref err) => Some(err),
+			Error::ZipError(ref err) => Some(err),
+			Error::FormatError { .. } => None
 		}
 	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct City {
+	pub name: String,
+	pub country: String,
+	pub population: i64
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CityCount {
+	pub name: String,
+	pub count: usize
+}
+
+impl From<(String, usize)> for CityCount {
+	fn from((name, count): (String, usize)) -> CityCount {
+		CityCount { name: name, count: count }
+	}
+}
+
+impl From<CityCount> for (String, usize) {
+	fn from(city: CityCount) -> (String, usize) {
+		(city.name, city.count)
+	}
+}
+
+// Indices of the used columns in the tab separated lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnLayout {
+	pub id: usize,
+	pub name: usize,
+	pub feature_class: usize,
+	pub country: usize,
+	pub population: usize
+}
+
+impl ColumnLayout {
 	
-	fn cause(&self) -> Option<&StdError> {
-		match *self {
-			Error::IoError(ref err) => Some(err),
-			Error::ParseError(ref err) => Some(err),
-			Error::ZipError(ref err) => Some(err)
+	// Lines with fewer columns are skipped
+	fn num_columns(&self) -> usize {
+		*[self.id, self.name, self.feature_class, self.country, self.population].iter().max().unwrap() + 1
+	}
+}
+
+// The column layout of the GeoNames dumps
+impl Default for ColumnLayout {
+	fn default() -> ColumnLayout {
+		ColumnLayout {
+			id: 0,
+			name: 1,
+			feature_class: 6,
+			country: 8,
+			population: 14
 		}
 	}
 }
 
-pub fn read_info_from_file(name: &str) -> Result<Vec<(String, String)>, Error> {
-	let file = File::open(name)?;
-	let mut archive = ZipArchive::new(file)?;
+// Filters for the cities which are read from a file.
+#[derive(Clone, Debug)]
+pub struct ReadOptions {
+	// Minimal population, an empty population column counts as 0
+	pub min_population: i64,
+	// Accepted GeoNames feature classes
+	pub feature_classes: HashSet<String>,
+	pub columns: ColumnLayout,
+	// Keep only the first record of each geonameid
+	pub dedup_by_id: bool,
+	// Fail on non-empty lines with too few columns instead of skipping them
+	pub strict: bool
+}
+
+impl Default for ReadOptions {
+	fn default() -> ReadOptions {
+		ReadOptions {
+			min_population: 1,
+			feature_classes: ["P"].iter().map(|class| class.to_string()).collect(),
+			columns: ColumnLayout::default(),
+			dedup_by_id: false,
+			strict: false
+		}
+	}
+}
+
+// Options for grouping the city names while counting.
+#[derive(Clone, Debug, Default)]
+pub struct CountOptions {
+	// Group names which only differ in case, the first spelling is kept
+	pub case_insensitive: bool,
+	// Group names which only differ in diacritics (ü -> u), the first
+	// spelling is kept
+	pub fold_ascii: bool
+}
+
+impl CountOptions {
 	
+	// Without grouping the name itself is the key
+	fn key<'n>(&self, name: &'n str) -> Cow<'n, str> {
+		let key: Cow<str> = if self.fold_ascii {
+			Cow::Owned(fold_ascii(name))
+		} else {
+			Cow::Borrowed(name)
+		};
+		
+		if self.case_insensitive {
+			Cow::Owned(key.to_lowercase())
+		} else {
+			key
+		}
+	}
+}
+
+// Replaces latin letters with diacritics by their base letters.
+// Other characters are kept.
+pub fn fold_ascii(name: &str) -> String {
+	let mut folded: String = String::with_capacity(name.len());
+	for c in name.chars() {
+		match fold_char(c) {
+			Some(replacement) => folded.push_str(replacement),
+			// Drop combining marks of decomposed characters
+			None if ('\u{0300}'..='\u{036f}').contains(&c) => { },
+			None => folded.push(c)
+		}
+	}
+	
+	return folded;
+}
+
+fn fold_char(c: char) -> Option<&'static str> {
+	let replacement = match c {
+		'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+		'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+		'Æ' => "AE",
+		'æ' => "ae",
+		'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+		'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+		'Ď' | 'Đ' | 'Ð' => "D",
+		'ď' | 'đ' | 'ð' => "d",
+		'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+		'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+		'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+		'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+		'Ĥ' | 'Ħ' => "H",
+		'ĥ' | 'ħ' => "h",
+		'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+		'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+		'Ĵ' => "J",
+		'ĵ' => "j",
+		'Ķ' => "K",
+		'ķ' => "k",
+		'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+		'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+		'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+		'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+		'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+		'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+		'Œ' => "OE",
+		'œ' => "oe",
+		'Ŕ' | 'Ŗ' | 'Ř' => "R",
+		'ŕ' | 'ŗ' | 'ř' => "r",
+		'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+		'ś' | 'ŝ' | 'ş' | 'š' => "s",
+		'ß' => "ss",
+		'Ţ' | 'Ť' | 'Ŧ' => "T",
+		'ţ' | 'ť' | 'ŧ' => "t",
+		'Þ' => "TH",
+		'þ' => "th",
+		'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+		'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+		'Ŵ' => "W",
+		'ŵ' => "w",
+		'Ý' | 'Ŷ' | 'Ÿ' => "Y",
+		'ý' | 'ÿ' | 'ŷ' => "y",
+		'Ź' | 'Ż' | 'Ž' => "Z",
+		'ź' | 'ż' | 'ž' => "z",
+		_ => { return None; }
+	};
+	
+	return Some(replacement);
+}
+
+pub fn read_info_from_file(name: &str) -> Result<Vec<(String, String)>, Error> {
+	read_info_from_file_with_min_population(name, 1)
+}
+
+// Reads the cities with at least the given population, an empty
+// population column counts as 0.
+pub fn read_info_from_file_with_min_population(name: &str, min_population: i64)
+		-> Result<Vec<(String, String)>, Error> {
+	read_info_from_file_with_options(name, &ReadOptions {
+		min_population: min_population,
+		..ReadOptions::default()
+	})
+}
+
+pub fn read_info_from_file_with_options(name: &str, options: &ReadOptions)
+		-> Result<Vec<(String, String)>, Error> {
 	let mut cities: Vec<(String, String)> = Vec::new();
 	cities.reserve(20000);
 	
+	for_each_city_in_file(name, options, |name, country, _| {
+		cities.push((name.to_owned(), country.to_owned()));
+	})?;
+	
+	Ok(cities)
+}
+
+#[allow(dead_code)]
+pub fn read_cities_from_file(name: &str, options: &ReadOptions) -> Result<Vec<City>, Error> {
+	let mut cities: Vec<City> = Vec::new();
+	cities.reserve(20000);
+	
+	for_each_city_in_file(name, options, |name, country, population| {
+		cities.push(City {
+			name: name.to_owned(),
+			country: country.to_owned(),
+			population: population
+		});
+	})?;
+	
+	Ok(cities)
+}
+
+pub fn read_info_from_reader<R: BufRead>(reader: R, options: &ReadOptions)
+		-> Result<Vec<(String, String)>, Error> {
+	let mut cities: Vec<(String, String)> = Vec::new();
+	read_lines(reader, options, &mut cities)?;
+	
+	Ok(cities)
+}
+
+// Reads the cities from the standard input, e.g. a decompressed dump.
+pub fn read_info_from_stdin(options: &ReadOptions) -> Result<Vec<(String, String)>, Error> {
+	let stdin = io::stdin();
+	let lock = stdin.lock();
+	read_info_from_reader(lock, options)
+}
+
+fn read_lines<R: BufRead>(buf: R, options: &ReadOptions, cities: &mut Vec<(String, String)>)
+		-> Result<(), Error> {
+	for_each_city(buf, options, &mut HashSet::new(), |name, country, _| {
+		cities.push((name.to_owned(), country.to_owned()));
+	})
+}
+
+fn for_each_city_in_file<F: FnMut(&str, &str, i64)>(name: &str, options: &ReadOptions, mut f: F)
+		-> Result<(), Error> {
+	let file = File::open(name)?;
+	
+	// Gzip files and plain text files contain a single table
+	if name.ends_with(".gz") {
+		let buf = BufReader::new(GzDecoder::new(file));
+		return for_each_city(buf, options, &mut HashSet::new(), f);
+	} else if name.ends_with(".txt") {
+		return for_each_city(BufReader::new(file), options, &mut HashSet::new(), f);
+	}
+	
+	let mut archive = ZipArchive::new(file)?;
+	
+	// Duplicates are removed across all files of the archive
+	let mut seen_ids: HashSet<u64> = HashSet::new();
 	for index in 0 .. archive.len() {
 		let entry = archive.by_index(index)?;
 		let buf = BufReader::new(entry);
-		read_lines(buf, &mut cities)?;
+		for_each_city(buf, options, &mut seen_ids, &mut f)?;
 	}
 	
-	Ok(cities)
+	Ok(())
 }
 
-fn read_lines<R: BufRead>(buf: R, cities: &mut Vec<(String, String)>) -> Result<(), Error> {
-	for line_res in buf.lines() {
+// Calls the function with the name, country and population of each
+// accepted city. The seen ids are only used to remove duplicates.
+fn for_each_city<R: BufRead, F: FnMut(&str, &str, i64)>(buf: R, options: &ReadOptions,
+		seen_ids: &mut HashSet<u64>, mut f: F) -> Result<(), Error> {
+	let columns: ColumnLayout = options.columns;
+	let num_columns: usize = columns.num_columns();
+	
+	for (index, line_res) in buf.lines().enumerate() {
 		let line = line_res?;
+		let mut line: &str = line.trim_end_matches('\r');
+		
+		// Files exported on Windows might start with a byte order mark
+		if index == 0 {
+			line = line.trim_start_matches('\u{feff}');
+		}
 		
 		let parts: Vec<&str> = line.split('\t').collect();
-		if parts.len() < 15 {
+		if parts.len() < num_columns {
+			if options.strict && !line.is_empty() {
+				return Err(Error::FormatError {
+					message: format!("Expected {} columns but found {} (line {})",
+						num_columns, parts.len(), index + 1)
+				});
+			}
+			continue;
+		}
+		
+		if options.dedup_by_id && !seen_ids.insert(parts[columns.id].parse::<u64>()?) {
 			continue;
 		}
 		
-		if parts[6] == "P" && parts[14].parse::<i64>()? > 0 {
-			cities.push((parts[1].to_owned(), parts[8].to_owned()));
+		if !options.feature_classes.contains(parts[columns.feature_class]) {
+			continue;
+		}
+		
+		let population: &str = parts[columns.population];
+		let population: i64 = if population.is_empty() { 0 } else { population.parse::<i64>()? };
+		if population >= options.min_population {
+			f(parts[columns.name], parts[columns.country], population);
 		}
 	}
 	
 	Ok(())
 }
 
+// Counts the city names while reading without storing all cities.
+// With a country a name counts with all of its occurrences if at least
+// one of them is in the country.
+#[allow(dead_code)]
+pub fn count_cities_streaming<R: BufRead>(reader: R, options: &ReadOptions, country: Option<&str>,
+		count_options: &CountOptions) -> Result<HashMap<String, usize>, Error> {
+	let mut counter = NameCounter::new(count_options);
+	for_each_city(reader, options, &mut HashSet::new(), |name, city_country, _| {
+		counter.add(name, 1, is_in_country(city_country, country));
+	})?;
+	
+	Ok(counter.into_counts().into_iter().collect())
+}
+
+// Same as count_cities_streaming for all files in a zip archive.
+pub fn count_cities_in_file(name: &str, options: &ReadOptions, country: Option<&str>,
+		count_options: &CountOptions) -> Result<HashMap<String, usize>, Error> {
+	let mut counter = NameCounter::new(count_options);
+	for_each_city_in_file(name, options, |name, city_country, _| {
+		counter.add(name, 1, is_in_country(city_country, country));
+	})?;
+	
+	Ok(counter.into_counts().into_iter().collect())
+}
+
+// Counts the city names, see CountOptions for the grouping of the names.
+// A name counts with all of its occurrences, but it is only returned if
+// at least one of them is in the country.
+struct NameCounter {
+	options: CountOptions,
+	// Maps the grouping key to the first spelling, the count and whether
+	// a city is in the country
+	names: HashMap<String, (String, usize, bool)>
+}
+
+impl NameCounter {
+	
+	fn new(options: &CountOptions) -> NameCounter {
+		NameCounter { options: options.clone(), names: HashMap::new() }
+	}
+	
+	fn add(&mut self, name: &str, count: usize, in_country: bool) {
+		let key: Cow<str> = self.options.key(name);
+		
+		// Only allocate the key and the name on the first occurrence
+		if let Some(value) = self.names.get_mut(&key[..]) {
+			value.1 += count;
+			value.2 |= in_country;
+			return;
+		}
+		self.names.insert(key.into_owned(), (name.to_owned(), count, in_country));
+	}
+	
+	// Adds the counts of the other counter, which has to count the cities
+	// after the ones of this counter with the same options
+	#[cfg(feature = "rayon")]
+	fn merge(mut self, mut other: NameCounter) -> NameCounter {
+		// Merge the smaller map into the larger one, the spellings of this
+		// counter are kept as they were seen first
+		let swapped: bool = self.names.len() < other.names.len();
+		if swapped {
+			::std::mem::swap(&mut self.names, &mut other.names);
+		}
+		
+		for (key, (name, count, in_country)) in other.names.drain() {
+			match self.names.entry(key) {
+				Entry::Occupied(mut o) => {
+					let value = o.get_mut();
+					if swapped {
+						value.0 = name;
+					}
+					value.1 += count;
+					value.2 |= in_country;
+				},
+				Entry::Vacant(v) => {
+					v.insert((name, count, in_country));
+				}
+			}
+		}
+		
+		self
+	}
+	
+	// Returns the names in the country with their counts in no particular order
+	fn into_counts(self) -> Vec<(String, usize)> {
+		self.names.into_iter()
+			.filter(|&(_, (_, _, in_country))| in_country)
+			.map(|(_, (name, count, _))| (name, count))
+			.collect()
+	}
+	
+	fn into_sorted_counts(self) -> Vec<(String, usize)> {
+		let mut names: Vec<(String, usize)> = self.into_counts();
+		sort_by_count(&mut names);
+		
+		return names;
+	}
+}
+
+// Without a country all cities are in the country
+fn is_in_country(city_country: &str, country: Option<&str>) -> bool {
+	country.map_or(true, |country| country == city_country)
+}
+
+// Counts the names of (name, country) pairs.
+fn count_names<'c, I: Iterator<Item = (&'c str, &'c str)>>(cities: I, country: Option<&str>,
+		options: &CountOptions) -> NameCounter {
+	let mut counter = NameCounter::new(options);
+	for (name, city_country) in cities {
+		counter.add(name, 1, is_in_country(city_country, country));
+	}
+	
+	return counter;
+}
+
+fn tuple_names<'c>(cities: &'c [(String, String)]) -> impl Iterator<Item = (&'c str, &'c str)> {
+	cities.iter().map(|city| (&city.0[..], &city.1[..]))
+}
+
+fn city_names<'c>(cities: &'c [City]) -> impl Iterator<Item = (&'c str, &'c str)> {
+	cities.iter().map(|city| (&city.name[..], &city.country[..]))
+}
+
+fn to_city_counts(names: Vec<(String, usize)>) -> Vec<CityCount> {
+	names.into_iter().map(CityCount::from).collect()
+}
+
+// Returns the first n names in the order of sort_by_count without
+// sorting all names.
+fn top_n(names: Vec<(String, usize)>, n: usize) -> Vec<(String, usize)> {
+	if n == 0 {
+		return Vec::new();
+	}
+	
+	// Min-heap of the best n names, the top is the worst of them
+	let mut heap: BinaryHeap<Reverse<(usize, Reverse<String>)>> = BinaryHeap::with_capacity(n + 1);
+	for (name, count) in names {
+		heap.push(Reverse((count, Reverse(name))));
+		if heap.len() > n {
+			heap.pop();
+		}
+	}
+	
+	return heap.into_sorted_vec().into_iter()
+		.map(|Reverse((count, Reverse(name)))| (name, count))
+		.collect();
+}
+
+// Returns the first name in the order of sort_by_count without sorting
+// all names.
+fn most_frequent(names: Vec<(String, usize)>) -> Option<(String, usize)> {
+	names.into_iter().min_by(compare_by_count)
+}
+
+// Sorts by descending count, ties are sorted by ascending name so the
+// order does not depend on the hash map. All results of the analyzer
+// use this order.
+pub fn sort_by_count<K: Ord, T: Ord>(names: &mut [(K, T)]) {
+	names.sort_by(compare_by_count);
+}
+
+fn compare_by_count<K: Ord, T: Ord>(a: &(K, T), b: &(K, T)) -> Ordering {
+	b.1.cmp(&a.1)
+		.then(a.0.cmp(&b.0))
+}
+
 pub fn compute_most_frequent_city_by_sorting(mut cities: Vec<(String, String)>)
 		-> Vec<(String, usize)> {
 	let length = cities.len();
@@ -124,45 +585,190 @@ pub fn compute_most_frequent_city_by_sorting(mut cities: Vec<(String, String)>)
 	}
 	
 	names.push((cities[length - 1].0.to_owned(), count));
-	names.sort_by(|a, b| b.1.cmp(&a.1)
-		.then(b.0.cmp(&a.0).reverse()));
+	sort_by_count(&mut names);
 	
 	return names;
 }
 
 pub fn compute_most_frequent_city_by_map(cities: &Vec<(String, String)>) -> Vec<(String, usize)> {
-	let length = cities.len();
-	if length == 0 {
-		return Vec::new();
+	count_names(tuple_names(cities), None, &CountOptions::default()).into_sorted_counts()
+}
+
+// Counts the cities of each country.
+#[allow(dead_code)]
+pub fn count_cities_per_country(cities: &[(String, String)]) -> Vec<(String, usize)> {
+	// The country codes are counted like names
+	count_names(cities.iter().map(|city| (&city.1[..], &city.1[..])), None, &CountOptions::default())
+		.into_sorted_counts()
+}
+
+// Counts the cities with the same name.
+#[allow(dead_code)]
+pub fn count_cities(cities: &[City], options: &CountOptions) -> Vec<CityCount> {
+	to_city_counts(count_names(city_names(cities), None, options).into_sorted_counts())
+}
+
+// Counts the cities with the same name if at least one of them is in the
+// given country.
+#[allow(dead_code)]
+pub fn count_cities_in_country(cities: &[City], country: &str, options: &CountOptions)
+		-> Vec<CityCount> {
+	to_city_counts(count_names(city_names(cities), Some(country), options).into_sorted_counts())
+}
+
+// Returns the n most frequent city names in the same order as
+// count_cities without sorting all names.
+#[allow(dead_code)]
+pub fn top_n_city_counts(cities: &[City], n: usize, options: &CountOptions) -> Vec<CityCount> {
+	to_city_counts(top_n(count_names(city_names(cities), None, options).into_counts(), n))
+}
+
+// Returns the first entry of count_cities without sorting all names.
+#[allow(dead_code)]
+pub fn most_frequent_city_count(cities: &[City], options: &CountOptions) -> Option<CityCount> {
+	most_frequent(count_names(city_names(cities), None, options).into_counts()).map(CityCount::from)
+}
+
+// Sums the population of all cities with the same name.
+#[allow(dead_code)]
+pub fn sum_population_by_city(cities: &[City]) -> Vec<(String, i64)> {
+	let mut map: HashMap<&str, i64> = HashMap::new();
+	for city in cities {
+		let name: &str = &city.name;
+		*map.entry(name).or_insert(0) += city.population;
+	}
+	
+	let mut names: Vec<(String, i64)> = Vec::new();
+	for (name, population) in map.drain() {
+		names.push((name.to_owned(), population));
 	}
+	sort_by_count(&mut names);
 	
-	let mut map: HashMap<&str, usize> = HashMap::new();
+	return names;
+}
+
+// Counts the cities in the population ranges between the bucket
+// boundaries. A range contains its lower boundary, so the boundaries
+// 1000 and 10000 give the ranges "<1000", "1000-10000" and ">=10000".
+#[allow(dead_code)]
+pub fn population_histogram(cities: &[City], buckets: &[i64]) -> Vec<(String, usize)> {
+	let mut boundaries: Vec<i64> = buckets.to_vec();
+	boundaries.sort();
+	boundaries.dedup();
+	
+	let mut counts: Vec<usize> = vec![0; boundaries.len() + 1];
 	for city in cities {
-		let name: &str = &city.0;
-		
-		match map.entry(name) {
-			Entry::Occupied(mut o) => {
-				let new_value = o.get() + 1;
-				o.insert(new_value);
-			},
-			Entry::Vacant(v) => {
-				v.insert(1);
-			}
-		}
+		let index = boundaries.iter().take_while(|&&boundary| boundary <= city.population).count();
+		counts[index] += 1;
 	}
 	
-	let mut names: Vec<(String, usize)> = Vec::new();
-	for (name, count) in map.drain() {
-		names.push((name.to_owned(), count));
+	let mut histogram: Vec<(String, usize)> = Vec::with_capacity(counts.len());
+	for (index, count) in counts.into_iter().enumerate() {
+		let label = if boundaries.is_empty() {
+			"all".to_owned()
+		} else if index == 0 {
+			format!("<{}", boundaries[0])
+		} else if index == boundaries.len() {
+			format!(">={}", boundaries[index - 1])
+		} else {
+			format!("{}-{}", boundaries[index - 1], boundaries[index])
+		};
+		histogram.push((label, count));
 	}
-	names.sort_by(|a, b| b.1.cmp(&a.1)
-		.then(b.0.cmp(&a.0).reverse()));
+	
+	return histogram;
+}
+
+#[allow(dead_code)]
+pub fn compute_most_frequent_city_with_options(cities: &[(String, String)], options: &CountOptions)
+		-> Vec<(String, usize)> {
+	count_names(tuple_names(cities), None, options).into_sorted_counts()
+}
+
+// Counts the cities by name and country, so cities with the same name
+// in different countries are not merged.
+pub fn compute_most_frequent_city_by_map_with_country(cities: &[(String, String)])
+		-> Vec<((String, String), usize)> {
+	let mut map: HashMap<(&str, &str), usize> = HashMap::new();
+	for city in cities {
+		let key: (&str, &str) = (&city.0, &city.1);
+		*map.entry(key).or_insert(0) += 1;
+	}
+	
+	let mut names: Vec<((String, String), usize)> = Vec::new();
+	for ((name, country), count) in map.drain() {
+		names.push(((name.to_owned(), country.to_owned()), count));
+	}
+	sort_by_count(&mut names);
 	
 	return names;
 }
 
-pub fn compute_most_frequent_city_by_sorting_in_de(mut cities: Vec<(String, String)>)
+// Sums the counts of two results, e.g. of different files.
+#[allow(dead_code)]
+pub fn merge_counts(a: Vec<(String, usize)>, b: Vec<(String, usize)>) -> Vec<(String, usize)> {
+	let mut counter = NameCounter::new(&CountOptions::default());
+	for (name, count) in a.into_iter().chain(b) {
+		counter.add(&name, count, true);
+	}
+	
+	return counter.into_sorted_counts();
+}
+
+// Times the sorting and the map strategy on the same cities. The flag is
+// true if both strategies return the same list.
+pub fn compare_strategies(cities: &[(String, String)]) -> (Duration, Duration, bool) {
+	// Copy the input first, the copy is not part of the sorting time
+	let city_set: Vec<(String, String)> = cities.to_vec();
+	
+	let start_time = Instant::now();
+	let names_sort = compute_most_frequent_city_by_sorting(city_set);
+	let sort_time = start_time.elapsed();
+	
+	let city_set: Vec<(String, String)> = cities.to_vec();
+	
+	let start_time = Instant::now();
+	let names_map = compute_most_frequent_city_by_map(&city_set);
+	let map_time = start_time.elapsed();
+	
+	return (sort_time, map_time, names_sort == names_map);
+}
+
+// Counts the names in parallel, each thread builds the map of a part of
+// the cities and the maps are merged afterwards.
+#[cfg(feature = "rayon")]
+pub fn compute_most_frequent_city_parallel(cities: &[(String, String)]) -> Vec<(String, usize)> {
+	// One part per thread, smaller parts only add merge work
+	let part_size = max(cities.len() / rayon::current_num_threads(), 1);
+	let options = CountOptions::default();
+	
+	let counter = cities.par_chunks(part_size)
+		.map(|part| count_names(tuple_names(part), None, &options))
+		.reduce(|| NameCounter::new(&options), NameCounter::merge);
+	
+	return counter.into_sorted_counts();
+}
+
+// Returns the most frequent city name without sorting all names.
+// On a tie the smallest name wins like in the compute_* functions. This
+// replaces the first rule, where the name occurring first in the list won,
+// because that result could differ from the first entry of the sorted lists.
+pub fn most_frequent_city(cities: &[(String, String)], options: &CountOptions)
+		-> Option<(String, usize)> {
+	most_frequent(count_names(tuple_names(cities), None, options).into_counts())
+}
+
+// Returns the n most frequent city names in the same order as the
+// compute_* functions without sorting all names.
+pub fn top_n_cities(cities: &[(String, String)], n: usize, options: &CountOptions)
 		-> Vec<(String, usize)> {
+	top_n(count_names(tuple_names(cities), None, options).into_counts(), n)
+}
+
+// The sorting strategies compare the exact names, the map based functions
+// also group the names with CountOptions.
+pub fn compute_most_frequent_city_by_sorting_in_country(mut cities: Vec<(String, String)>,
+		country: &str) -> Vec<(String, usize)> {
 	let length = cities.len();
 	if length == 0 {
 		return Vec::new();
@@ -173,67 +779,51 @@ pub fn compute_most_frequent_city_by_sorting_in_de(mut cities: Vec<(String, Stri
 	let mut names: Vec<(String, usize)> = Vec::new();
 	let mut last_name: &str = &cities[0].0;
 	let mut count: usize = 1;
-	let mut in_de: bool = &cities[0].0 == "DE";
+	let mut in_country: bool = cities[0].1 == country;
 	
 	for city in cities.iter().skip(1) {
 		if city.0 == last_name {
 			count += 1;
-			in_de |= city.1 == "DE";
+			in_country |= city.1 == country;
 		} else {
-			if in_de {
+			if in_country {
 				names.push((last_name.to_owned(), count));
 			}
 			
 			last_name = &city.0;
 			count = 1;
-			in_de = city.1 == "DE";
+			in_country = city.1 == country;
 		}
 	}
 	
-	if in_de || cities[length - 1].1 == "DE" {
+	if in_country {
 		names.push((cities[length - 1].0.to_owned(), count));
 	}
 	
-	names.sort_by(|a, b| b.1.cmp(&a.1)
-		.then(b.0.cmp(&a.0).reverse()));
+	sort_by_count(&mut names);
 	
 	return names;
 }
 
+// A name counts with all of its occurrences if at least one of them is
+// in the given country.
+pub fn compute_most_frequent_city_in_country(cities: &[(String, String)], country: &str,
+		options: &CountOptions) -> Vec<(String, usize)> {
+	count_names(tuple_names(cities), Some(country), options).into_sorted_counts()
+}
+
+#[allow(dead_code)]
+#[deprecated(note = "use compute_most_frequent_city_by_sorting_in_country")]
+pub fn compute_most_frequent_city_by_sorting_in_de(cities: Vec<(String, String)>)
+		-> Vec<(String, usize)> {
+	compute_most_frequent_city_by_sorting_in_country(cities, "DE")
+}
+
+#[allow(dead_code)]
+#[deprecated(note = "use compute_most_frequent_city_in_country")]
 pub fn compute_most_frequent_city_by_map_in_de(cities: &Vec<(String, String)>)
 		-> Vec<(String, usize)> {
-	let length = cities.len();
-	if length == 0 {
-		return Vec::new();
-	}
-	
-	let mut map: HashMap<&str, (usize, bool)> = HashMap::new();
-	for city in cities {
-		let name: &str = &city.0;
-		
-		match map.entry(name) {
-			Entry::Occupied(mut o) => {
-				let old_value = o.get_mut();
-				old_value.0 += 1;
-				old_value.1 |= city.1 == "DE";
-			},
-			Entry::Vacant(v) => {
-				v.insert((1, city.1 == "DE"));
-			}
-		}
-	}
-	
-	let mut names: Vec<(String, usize)> = Vec::new();
-	for (name, (count, in_de)) in map.drain() {
-		if in_de {
-			names.push((name.to_owned(), count));
-		}
-	}
-	
-	names.sort_by(|a, b| b.1.cmp(&a.1)
-		.then(b.0.cmp(&a.0).reverse()));
-	
-	return names;
+	compute_most_frequent_city_in_country(cities, "DE", &CountOptions::default())
 }
 
 #[test]
@@ -265,6 +855,7 @@ fn test_analyzer() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_analyzer_de() {
 	if let Ok(data) = read_info_from_file("test_data.zip") {
 		let cities_map = compute_most_frequent_city_by_map_in_de(&data);
@@ -282,4 +873,580 @@ fn test_analyzer_de() {
 	} else {
 		assert!(false);
 	}
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_read_bom_crlf() {
+	let data = "\u{feff}0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\r\n\
+		1\tFreiburg\t\t\t\t\tP\t\tUS\t\t\t\t\t\t5\r\n\
+		2\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t7\r\n";
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions::default(), &mut cities).unwrap();
+	
+	assert_eq!(3, cities.len());
+	assert_eq!(("Freiburg".to_owned(), "DE".to_owned()), cities[0]);
+	assert_eq!(vec![("Freiburg".to_owned(), 2_usize)],
+		compute_most_frequent_city_by_map_in_de(&cities));
+}
+
+#[test]
+fn test_error_source() {
+	let err = read_info_from_file("missing.zip").err().unwrap();
+	
+	let source = err.source().unwrap();
+	assert_eq!(::std::io::ErrorKind::NotFound,
+		source.downcast_ref::<IOError>().unwrap().kind());
+}
+
+#[test]
+fn test_most_frequent_city() {
+	if let Ok(data) = read_info_from_file("test_data.zip") {
+		let cities_sort = compute_most_frequent_city_by_sorting(data.clone());
+		assert_eq!(Some(cities_sort[0].clone()), most_frequent_city(&data, &CountOptions::default()));
+	} else {
+		assert!(false);
+	}
+	
+	assert_eq!(None, most_frequent_city(&[], &CountOptions::default()));
+	
+	// The smallest name of a tie wins, not the first one
+	let cities: Vec<(String, String)> = ["Freiburg", "Basel", "Basel", "Freiburg", "Bern"].iter()
+		.map(|name| (name.to_string(), "DE".to_owned()))
+		.collect();
+	assert_eq!(Some(("Basel".to_owned(), 2_usize)), most_frequent_city(&cities, &CountOptions::default()));
+	assert_eq!(compute_most_frequent_city_by_map(&cities)[0], most_frequent_city(&cities, &CountOptions::default()).unwrap());
+}
+
+#[test]
+fn test_top_n_cities() {
+	let names = ["Köln", "Basel", "Köln", "Bern", "Freiburg", "Basel", "Köln", "Zürich",
+		"Genf", "Freiburg", "Aachen", "Ulm", "Basel"];
+	let cities: Vec<(String, String)> = names.iter()
+		.map(|name| (name.to_string(), "DE".to_owned()))
+		.collect();
+	
+	let expected = vec![
+		("Basel".to_owned(), 3_usize),
+		("Köln".to_owned(), 3_usize),
+		("Freiburg".to_owned(), 2_usize)
+	];
+	assert_eq!(expected, top_n_cities(&cities, 3, &CountOptions::default()));
+	assert_eq!(compute_most_frequent_city_by_map(&cities)[..3].to_vec(), top_n_cities(&cities, 3, &CountOptions::default()));
+	
+	assert_eq!(8, top_n_cities(&cities, 20, &CountOptions::default()).len());
+	assert!(top_n_cities(&cities, 0, &CountOptions::default()).is_empty());
+}
+
+#[test]
+fn test_analyzer_country() {
+	if let Ok(data) = read_info_from_file("test_data.zip") {
+		#[allow(deprecated)]
+		let cities_de = compute_most_frequent_city_by_map_in_de(&data);
+		assert_eq!(cities_de, compute_most_frequent_city_in_country(&data, "DE", &CountOptions::default()));
+	} else {
+		assert!(false);
+	}
+	
+	let cities: Vec<(String, String)> = [("Paris", "FR"), ("Paris", "US"), ("Lyon", "FR"),
+			("Paris", "US"), ("Rom", "IT"), ("Rom", "IT"), ("Nice", "FR")].iter()
+		.map(|&(name, country)| (name.to_owned(), country.to_owned()))
+		.collect();
+	
+	// Paris counts with all three occurrences
+	let expected = vec![
+		("Paris".to_owned(), 3_usize),
+		("Lyon".to_owned(), 1_usize),
+		("Nice".to_owned(), 1_usize)
+	];
+	assert_eq!(expected, compute_most_frequent_city_in_country(&cities, "FR", &CountOptions::default()));
+	assert_eq!(expected, compute_most_frequent_city_by_sorting_in_country(cities.clone(), "FR"));
+	assert!(compute_most_frequent_city_in_country(&cities, "ES", &CountOptions::default()).is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_analyzer_parallel() {
+	if let Ok(data) = read_info_from_file("test_data.zip") {
+		assert_eq!(compute_most_frequent_city_by_map(&data), compute_most_frequent_city_parallel(&data));
+	} else {
+		assert!(false);
+	}
+	
+	let cities: Vec<(String, String)> = (0..50000_usize)
+		.map(|index| (format!("City {}", (index * 7919) % 1733), "DE".to_owned()))
+		.collect();
+	
+	let sequential: HashMap<String, usize> = compute_most_frequent_city_by_map(&cities).into_iter().collect();
+	let parallel: HashMap<String, usize> = compute_most_frequent_city_parallel(&cities).into_iter().collect();
+	assert_eq!(1733, parallel.len());
+	assert_eq!(sequential, parallel);
+	
+	assert!(compute_most_frequent_city_parallel(&[]).is_empty());
+}
+
+#[test]
+fn test_read_min_population() {
+	let data = "0\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t0\n\
+		1\tBern\t\t\t\t\tP\t\tCH\t\t\t\t\t\t50\n\
+		2\tZürich\t\t\t\t\tP\t\tCH\t\t\t\t\t\t500000\n\
+		3\tGenf\t\t\t\t\tP\t\tCH\t\t\t\t\t\t\n";
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions { min_population: 100, ..ReadOptions::default() },
+		&mut cities).unwrap();
+	assert_eq!(vec![("Zürich".to_owned(), "CH".to_owned())], cities);
+	
+	// The empty population of Genf counts as 0
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions { min_population: 0, ..ReadOptions::default() },
+		&mut cities).unwrap();
+	assert_eq!(4, cities.len());
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions { min_population: 1, ..ReadOptions::default() },
+		&mut cities).unwrap();
+	assert_eq!(2, cities.len());
+	
+	if let Ok(data) = read_info_from_file_with_min_population("test_data.zip", 1) {
+		assert_eq!(16, data.len());
+	} else {
+		assert!(false);
+	}
+}
+
+#[test]
+fn test_read_feature_classes() {
+	let data = "0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		1\tBaden-Württemberg\t\t\t\t\tA\t\tDE\t\t\t\t\t\t5\n\
+		2\tFeldberg\t\t\t\t\tT\t\tDE\t\t\t\t\t\t5\n";
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions::default(), &mut cities).unwrap();
+	assert_eq!(vec![("Freiburg".to_owned(), "DE".to_owned())], cities);
+	
+	let options = ReadOptions {
+		feature_classes: ["P", "A"].iter().map(|class| class.to_string()).collect(),
+		..ReadOptions::default()
+	};
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &options, &mut cities).unwrap();
+	assert_eq!(2, cities.len());
+	assert_eq!(("Baden-Württemberg".to_owned(), "DE".to_owned()), cities[1]);
+}
+
+#[test]
+fn test_analyzer_with_country() {
+	let cities: Vec<(String, String)> = [("Springfield", "US"), ("Springfield", "CA"),
+			("Springfield", "US"), ("Paris", "FR")].iter()
+		.map(|&(name, country)| (name.to_owned(), country.to_owned()))
+		.collect();
+	
+	let expected = vec![
+		(("Springfield".to_owned(), "US".to_owned()), 2_usize),
+		(("Paris".to_owned(), "FR".to_owned()), 1_usize),
+		(("Springfield".to_owned(), "CA".to_owned()), 1_usize)
+	];
+	assert_eq!(expected, compute_most_frequent_city_by_map_with_country(&cities));
+	assert_eq!(("Springfield".to_owned(), 3_usize), compute_most_frequent_city_by_map(&cities)[0]);
+	assert!(compute_most_frequent_city_by_map_with_country(&[]).is_empty());
+}
+
+#[test]
+fn test_count_cities_streaming() {
+	let data = "0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		1\tFreiburg\t\t\t\t\tP\t\tCH\t\t\t\t\t\t5\n\
+		2\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t7\n\
+		3\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t0\n\
+		4\tKöln\t\t\t\t\tP\t\tDE\t\t\t\t\t\t9\n\
+		5\tFreiburg\t\t\t\t\tP\t\tUS\t\t\t\t\t\t5\n";
+	let options = ReadOptions::default();
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &options, &mut cities).unwrap();
+	
+	let expected: HashMap<String, usize> = compute_most_frequent_city_by_map(&cities).into_iter().collect();
+	assert_eq!(expected, count_cities_streaming(Cursor::new(data), &options, None, &CountOptions::default()).unwrap());
+	
+	let expected: HashMap<String, usize> = compute_most_frequent_city_in_country(&cities, "DE", &CountOptions::default()).into_iter().collect();
+	let counts = count_cities_streaming(Cursor::new(data), &options, Some("DE"), &CountOptions::default()).unwrap();
+	assert_eq!(expected, counts);
+	assert_eq!(Some(&3), counts.get("Freiburg"));
+	assert_eq!(None, counts.get("Basel"));
+	
+	if let Ok(data) = read_info_from_file("test_data.zip") {
+		let expected: HashMap<String, usize> = compute_most_frequent_city_by_map(&data).into_iter().collect();
+		assert_eq!(expected, count_cities_in_file("test_data.zip", &options, None, &CountOptions::default()).unwrap());
+	} else {
+		assert!(false);
+	}
+}
+
+#[test]
+fn test_analyzer_case_insensitive() {
+	let cities: Vec<(String, String)> = ["München", "MÜNCHEN", "Köln", "münchen", "KÖLN"].iter()
+		.map(|name| (name.to_string(), "DE".to_owned()))
+		.collect();
+	
+	let options = CountOptions { case_insensitive: true, ..CountOptions::default() };
+	let expected = vec![
+		("München".to_owned(), 3_usize),
+		("Köln".to_owned(), 2_usize)
+	];
+	assert_eq!(expected, compute_most_frequent_city_with_options(&cities, &options));
+	
+	assert_eq!(compute_most_frequent_city_by_map(&cities),
+		compute_most_frequent_city_with_options(&cities, &CountOptions::default()));
+	assert_eq!(5, compute_most_frequent_city_with_options(&cities, &CountOptions::default()).len());
+	
+	// All counting functions group the names in the same way
+	assert_eq!(expected, compute_most_frequent_city_in_country(&cities, "DE", &options));
+	assert_eq!(expected[..1].to_vec(), top_n_cities(&cities, 1, &options));
+	assert_eq!(Some(expected[0].clone()), most_frequent_city(&cities, &options));
+	
+	let city_structs: Vec<City> = cities.iter()
+		.map(|city| City { name: city.0.clone(), country: city.1.clone(), population: 1 })
+		.collect();
+	let expected_counts: Vec<CityCount> = expected.iter().cloned().map(CityCount::from).collect();
+	assert_eq!(expected_counts, count_cities(&city_structs, &options));
+	assert_eq!(expected_counts, count_cities_in_country(&city_structs, "DE", &options));
+	
+	// The first spelling is kept even if it is not in the country
+	let data = "0\tMÜNCHEN\t\t\t\t\tP\t\tAT\t\t\t\t\t\t5\n\
+		1\tMünchen\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		2\tKöln\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n";
+	let counts = count_cities_streaming(Cursor::new(data), &ReadOptions::default(), Some("DE"),
+		&options).unwrap();
+	assert_eq!(2, counts.len());
+	assert_eq!(Some(&2), counts.get("MÜNCHEN"));
+}
+
+#[test]
+fn test_analyzer_fold_ascii() {
+	let cities: Vec<(String, String)> = ["Zürich", "Zurich", "Düsseldorf", "Zu\u{308}rich", "ZURICH"].iter()
+		.map(|name| (name.to_string(), "CH".to_owned()))
+		.collect();
+	
+	assert_eq!(5, compute_most_frequent_city_by_map(&cities).len());
+	
+	let options = CountOptions { fold_ascii: true, ..CountOptions::default() };
+	let expected = vec![
+		("Zürich".to_owned(), 3_usize),
+		("Düsseldorf".to_owned(), 1_usize),
+		("ZURICH".to_owned(), 1_usize)
+	];
+	assert_eq!(expected, compute_most_frequent_city_with_options(&cities, &options));
+	assert_eq!(expected, compute_most_frequent_city_in_country(&cities, "CH", &options));
+	assert_eq!(expected[..2].to_vec(), top_n_cities(&cities, 2, &options));
+	assert_eq!(Some(expected[0].clone()), most_frequent_city(&cities, &options));
+	
+	let data = "0\tZürich\t\t\t\t\tP\t\tCH\t\t\t\t\t\t5\n\
+		1\tZurich\t\t\t\t\tP\t\tUS\t\t\t\t\t\t5\n\
+		2\tDusseldorf\t\t\t\t\tP\t\tUS\t\t\t\t\t\t5\n\
+		3\tDüsseldorf\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n";
+	let counts = count_cities_streaming(Cursor::new(data), &ReadOptions::default(), Some("US"),
+		&options).unwrap();
+	assert_eq!(2, counts.len());
+	assert_eq!(Some(&2), counts.get("Zürich"));
+	assert_eq!(Some(&2), counts.get("Dusseldorf"));
+	
+	// Without folding only the exact names are grouped
+	let counts = count_cities_streaming(Cursor::new(data), &ReadOptions::default(), Some("US"),
+		&CountOptions::default()).unwrap();
+	assert_eq!(Some(&1), counts.get("Zurich"));
+	assert_eq!(None, counts.get("Zürich"));
+	
+	let options = CountOptions { fold_ascii: true, case_insensitive: true };
+	assert_eq!(vec![("Zürich".to_owned(), 4_usize), ("Düsseldorf".to_owned(), 1_usize)],
+		compute_most_frequent_city_with_options(&cities, &options));
+	
+	assert_eq!("Strasse Lodz AEro", fold_ascii("Straße Łódź Æro"));
+}
+
+#[test]
+fn test_read_column_layout() {
+	let data = "DE\t5\tFreiburg\tP\n\
+		CH\t7\tBasel\tP\n\
+		CH\t7\tBern\n\
+		DE\t0\tKöln\tP\n";
+	let options = ReadOptions {
+		columns: ColumnLayout { id: 0, name: 2, feature_class: 3, country: 0, population: 1 },
+		..ReadOptions::default()
+	};
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &options, &mut cities).unwrap();
+	assert_eq!(vec![
+		("Freiburg".to_owned(), "DE".to_owned()),
+		("Basel".to_owned(), "CH".to_owned())
+	], cities);
+	
+	// The GeoNames layout needs 15 columns
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions::default(), &mut cities).unwrap();
+	assert!(cities.is_empty());
+}
+
+#[test]
+fn test_sum_population_by_city() {
+	let cities: Vec<City> = [("Riverside", "US", 10000), ("Springfield", "US", 12000),
+			("Riverside", "CA", 5000)].iter()
+		.map(|&(name, country, population)| City {
+			name: name.to_owned(),
+			country: country.to_owned(),
+			population: population
+		})
+		.collect();
+	
+	assert_eq!(vec![("Riverside".to_owned(), 15000), ("Springfield".to_owned(), 12000)],
+		sum_population_by_city(&cities));
+}
+
+#[test]
+fn test_read_dedup_by_id() {
+	let data = "7\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		8\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t7\n\
+		7\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		9\tFreiburg\t\t\t\t\tP\t\tCH\t\t\t\t\t\t5\n";
+	
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &ReadOptions::default(), &mut cities).unwrap();
+	assert_eq!(("Freiburg".to_owned(), 3_usize), compute_most_frequent_city_by_map(&cities)[0]);
+	
+	let options = ReadOptions { dedup_by_id: true, ..ReadOptions::default() };
+	let mut cities = Vec::new();
+	read_lines(Cursor::new(data), &options, &mut cities).unwrap();
+	assert_eq!(3, cities.len());
+	assert_eq!(("Freiburg".to_owned(), 2_usize), compute_most_frequent_city_by_map(&cities)[0]);
+	
+	let counts = count_cities_streaming(Cursor::new(data), &options, None, &CountOptions::default()).unwrap();
+	assert_eq!(Some(&2), counts.get("Freiburg"));
+}
+
+#[test]
+fn test_count_cities_per_country() {
+	let cities: Vec<(String, String)> = [("Freiburg", "DE"), ("Basel", "CH"), ("Köln", "DE"),
+			("Paris", "FR"), ("Bern", "CH"), ("Ulm", "DE")].iter()
+		.map(|&(name, country)| (name.to_owned(), country.to_owned()))
+		.collect();
+	
+	let expected = vec![
+		("DE".to_owned(), 3_usize),
+		("CH".to_owned(), 2_usize),
+		("FR".to_owned(), 1_usize)
+	];
+	assert_eq!(expected, count_cities_per_country(&cities));
+	assert!(count_cities_per_country(&[]).is_empty());
+}
+
+#[test]
+fn test_city_structs() {
+	if let Ok(cities) = read_cities_from_file("test_data.zip", &ReadOptions::default()) {
+		assert_eq!(16, cities.len());
+		assert!(cities.iter().all(|city| city.population > 0));
+		
+		let names = read_info_from_file("test_data.zip").unwrap();
+		assert_eq!(names[0], (cities[0].name.clone(), cities[0].country.clone()));
+		
+		let counts = count_cities(&cities, &CountOptions::default());
+		assert_eq!(CityCount { name: "Köln".to_owned(), count: 4 }, counts[0]);
+		assert_eq!(counts[..3].to_vec(), top_n_city_counts(&cities, 3, &CountOptions::default()));
+		assert_eq!(Some(counts[0].clone()), most_frequent_city_count(&cities, &CountOptions::default()));
+		
+		let expected: Vec<CityCount> = compute_most_frequent_city_by_map(&names).into_iter()
+			.map(CityCount::from)
+			.collect();
+		assert_eq!(expected, counts);
+		
+		let expected: Vec<CityCount> = compute_most_frequent_city_in_country(&names, "DE", &CountOptions::default()).into_iter()
+			.map(CityCount::from)
+			.collect();
+		assert_eq!(expected, count_cities_in_country(&cities, "DE", &CountOptions::default()));
+	} else {
+		assert!(false);
+	}
+	
+	let count: (String, usize) = CityCount { name: "Ulm".to_owned(), count: 2 }.into();
+	assert_eq!(("Ulm".to_owned(), 2_usize), count);
+}
+
+#[test]
+fn test_analyzer_ties() {
+	let names = ["Ulm", "Bern", "Köln", "Ulm", "Aalen", "Köln", "Bern", "Aalen", "Basel"];
+	let cities: Vec<(String, String)> = names.iter()
+		.map(|name| (name.to_string(), "DE".to_owned()))
+		.collect();
+	
+	let expected = vec![
+		("Aalen".to_owned(), 2_usize),
+		("Bern".to_owned(), 2_usize),
+		("Köln".to_owned(), 2_usize),
+		("Ulm".to_owned(), 2_usize),
+		("Basel".to_owned(), 1_usize)
+	];
+	
+	// The hash map order changes between runs
+	for _ in 0..10 {
+		assert_eq!(expected, compute_most_frequent_city_by_map(&cities));
+		assert_eq!(expected, compute_most_frequent_city_by_sorting(cities.clone()));
+		assert_eq!(expected, compute_most_frequent_city_in_country(&cities, "DE", &CountOptions::default()));
+		assert_eq!(expected, compute_most_frequent_city_by_sorting_in_country(cities.clone(), "DE"));
+		assert_eq!(expected[..3].to_vec(), top_n_cities(&cities, 3, &CountOptions::default()));
+		assert_eq!(Some(expected[0].clone()), most_frequent_city(&cities, &CountOptions::default()));
+	}
+}
+
+#[test]
+fn test_read_gzip_and_text() {
+	use self::flate2::Compression;
+	use self::flate2::write::GzEncoder;
+	
+	let data = "0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		1\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t7\n\
+		2\tFreiburg\t\t\t\t\tP\t\tCH\t\t\t\t\t\t5\n";
+	let expected = read_info_from_reader(Cursor::new(data), &ReadOptions::default()).unwrap();
+	assert_eq!(3, expected.len());
+	
+	// The process id keeps the names of concurrent test runs apart
+	let prefix = format!("ex04_test_read_{}", process::id());
+	let gzip_path = env::temp_dir().join(format!("{}.txt.gz", prefix));
+	let text_path = env::temp_dir().join(format!("{}.txt", prefix));
+	let invalid_path = env::temp_dir().join(format!("{}_invalid.txt.gz", prefix));
+	
+	let mut encoder = GzEncoder::new(File::create(&gzip_path).unwrap(), Compression::default());
+	encoder.write_all(data.as_bytes()).unwrap();
+	encoder.finish().unwrap();
+	File::create(&text_path).unwrap().write_all(data.as_bytes()).unwrap();
+	File::create(&invalid_path).unwrap().write_all(data.as_bytes()).unwrap();
+	
+	let name = gzip_path.to_str().unwrap();
+	let gzip_cities = read_info_from_file(name);
+	let gzip_counts = count_cities_in_file(name, &ReadOptions::default(), None, &CountOptions::default());
+	let text_cities = read_info_from_file(text_path.to_str().unwrap());
+	let invalid_cities = read_info_from_file(invalid_path.to_str().unwrap());
+	
+	// Remove the files before the checks can fail
+	for path in [&gzip_path, &text_path, &invalid_path].iter() {
+		fs::remove_file(path).unwrap();
+	}
+	
+	assert_eq!(expected, gzip_cities.unwrap());
+	assert_eq!(Some(&2), gzip_counts.unwrap().get("Freiburg"));
+	assert_eq!(expected, text_cities.unwrap());
+	
+	// Plain text is no valid gzip stream
+	match invalid_cities {
+		Err(Error::IoError(_)) => { },
+		_ => { assert!(false); }
+	}
+}
+
+#[test]
+fn test_read_info_from_reader() {
+	// A cursor stands in for the standard input
+	let stdin = "0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t500\n\
+		1\tFreiburg\t\t\t\t\tP\t\tCH\t\t\t\t\t\t50\n\
+		2\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t700\n\
+		3\tKöln\t\t\t\t\tP\t\tDE\t\t\t\t\t\t900\n";
+	
+	let options = ReadOptions { min_population: 100, ..ReadOptions::default() };
+	let cities = read_info_from_reader(Cursor::new(stdin), &options).unwrap();
+	assert_eq!(3, cities.len());
+	
+	let expected = vec![
+		("Freiburg".to_owned(), 1_usize),
+		("Köln".to_owned(), 1_usize)
+	];
+	assert_eq!(expected, compute_most_frequent_city_in_country(&cities, "DE", &CountOptions::default()));
+	
+	let cities = read_info_from_reader(Cursor::new(stdin), &ReadOptions::default()).unwrap();
+	assert_eq!(("Freiburg".to_owned(), 2_usize), compute_most_frequent_city_in_country(&cities, "DE", &CountOptions::default())[0]);
+}
+
+#[test]
+fn test_compare_strategies() {
+	if let Ok(data) = read_info_from_file("test_data.zip") {
+		let (_, _, same_result) = compare_strategies(&data);
+		assert!(same_result);
+	} else {
+		assert!(false);
+	}
+	
+	let cities: Vec<(String, String)> = (0..10000_usize)
+		.map(|index| (format!("City {}", (index * 7919) % 997), "DE".to_owned()))
+		.collect();
+	let (sort_time, map_time, same_result) = compare_strategies(&cities);
+	assert!(same_result);
+	assert!(sort_time > Duration::from_secs(0) && map_time > Duration::from_secs(0));
+	
+	assert!(compare_strategies(&[]).2);
+}
+
+#[test]
+fn test_population_histogram() {
+	let cities: Vec<City> = [50, 999, 1000, 5000, 20000, 99999, 100000, 3500000, 12].iter()
+		.map(|&population| City {
+			name: "Freiburg".to_owned(),
+			country: "DE".to_owned(),
+			population: population
+		})
+		.collect();
+	
+	let expected = vec![
+		("<1000".to_owned(), 3_usize),
+		("1000-10000".to_owned(), 2_usize),
+		("10000-100000".to_owned(), 2_usize),
+		(">=100000".to_owned(), 2_usize)
+	];
+	assert_eq!(expected, population_histogram(&cities, &[1000, 10000, 100000]));
+	assert_eq!(expected, population_histogram(&cities, &[100000, 1000, 10000, 1000]));
+	
+	assert_eq!(vec![("all".to_owned(), 9_usize)], population_histogram(&cities, &[]));
+	assert_eq!(vec![("<10".to_owned(), 0_usize), (">=10".to_owned(), 0_usize)],
+		population_histogram(&[], &[10]));
+}
+
+#[test]
+fn test_merge_counts() {
+	let a = vec![("X".to_owned(), 2_usize)];
+	let b = vec![("X".to_owned(), 3_usize), ("Y".to_owned(), 1_usize)];
+	assert_eq!(vec![("X".to_owned(), 5_usize), ("Y".to_owned(), 1_usize)], merge_counts(a, b));
+	
+	// Merging the counts of two halves gives the counts of the whole
+	if let Ok(data) = read_info_from_file("test_data.zip") {
+		let (first, second) = data.split_at(data.len() / 2);
+		assert_eq!(compute_most_frequent_city_by_map(&data),
+			merge_counts(compute_most_frequent_city_by_map(&first.to_vec()),
+				compute_most_frequent_city_by_map(&second.to_vec())));
+	} else {
+		assert!(false);
+	}
+	
+	assert!(merge_counts(Vec::new(), Vec::new()).is_empty());
+}
+
+#[test]
+fn test_read_strict() {
+	let data = "0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\
+		\n\
+		1\tBasel\t\t\t\t\tP\t\tCH\t\n\
+		2\tBasel\t\t\t\t\tP\t\tCH\t\t\t\t\t\t7\n";
+	
+	let cities = read_info_from_reader(Cursor::new(data), &ReadOptions::default()).unwrap();
+	assert_eq!(2, cities.len());
+	
+	let options = ReadOptions { strict: true, ..ReadOptions::default() };
+	match read_info_from_reader(Cursor::new(data), &options) {
+		Err(Error::FormatError { message }) => {
+			assert!(message.contains("found 10"));
+			assert!(message.contains("line 3"));
+		},
+		_ => { assert!(false); }
+	}
+	
+	// Empty lines are no error
+	let data = "0\tFreiburg\t\t\t\t\tP\t\tDE\t\t\t\t\t\t5\n\n";
+	assert_eq!(1, read_info_from_reader(Cursor::new(data), &options).unwrap().len());
 }
\ No newline at end of file
@@ -4,15 +4,26 @@ extern crate time;
 mod analyzer;
 
 use analyzer::read_info_from_file;
-use analyzer::compute_most_frequent_city_by_sorting;
-use analyzer::compute_most_frequent_city_by_sorting_in_de;
-use analyzer::compute_most_frequent_city_by_map;
-use analyzer::compute_most_frequent_city_by_map_in_de;
+use analyzer::read_info_from_stdin;
+use analyzer::ReadOptions;
+use analyzer::CountOptions;
+use analyzer::compare_strategies;
+use analyzer::compute_most_frequent_city_by_sorting_in_country;
+use analyzer::compute_most_frequent_city_by_map_with_country;
+use analyzer::count_cities_in_file;
+#[cfg(feature = "rayon")]
+use analyzer::compute_most_frequent_city_parallel;
+use analyzer::compute_most_frequent_city_in_country;
+use analyzer::most_frequent_city;
+use analyzer::sort_by_count;
+use analyzer::top_n_cities;
 
 use getopts::Options;
 use std::env;
 
 const FILE: &'static str = "allCountries.zip";
+const COUNTRY: &'static str = "DE";
+const STDIN_FILE: &'static str = "-";
 
 fn print_usage(program: &str, opts: Options) {
     println!("{}", opts.usage(&format!("Usage: {} [options]", program)));
@@ -24,7 +35,8 @@ fn main() {
 	
 	let mut opts = Options::new();
 	opts.optflag("h", "help", "Shows the help for this program.");
-	opts.optopt("f", "file", "The file to read from.", "FILE");
+	opts.optopt("f", "file", "The file to read from, - reads from the standard input.", "FILE");
+	opts.optopt("c", "country", "The country code to filter by (default DE).", "COUNTRY");
 	
 	let matches = match opts.parse(&args[1..]) {
 		Ok(m) => { m },
@@ -47,51 +59,116 @@ fn main() {
 		}
 	}
 	
+	let country: String = matches.opt_str("c").unwrap_or(COUNTRY.to_owned());
+	
 	println!("Loading input file");
 	
 	let mut start_time = time::get_time();
-	match read_info_from_file(&file) {
+	let result = if file == STDIN_FILE {
+		read_info_from_stdin(&ReadOptions::default())
+	} else {
+		read_info_from_file(&file)
+	};
+	
+	match result {
 		Ok(cities) => {
 			print_time("Loaded city names in ", "", time::get_time() - start_time);
 			println!();
 			
 			// World-Wide
-			// --------------- List --------------------
+			// --------------- List and map --------------------
 			
-			let city_set = cities.clone();
+			let (sort_time, map_time, same_names) = compare_strategies(&cities);
+			print_time("Listed city names by sorting in ", "", time::Duration::from_std(sort_time).unwrap());
+			print_time("Listed city names by map in ", "", time::Duration::from_std(map_time).unwrap());
+			println!("Both strategies listed the same names: {}", same_names);
+			
+			println!();
+			
+			// --------------- Map with country --------------------
 			
 			start_time = time::get_time();
-			let names = compute_most_frequent_city_by_sorting(city_set);
-			print_time("Listed city names by sorting in ", "", time::get_time() - start_time);
+			let names = compute_most_frequent_city_by_map_with_country(&cities);
+			print_time("Listed city names with country by map in ", "", time::get_time() - start_time);
 			
 			for name in names.iter().take(3).enumerate() {
-				println!("{}: {} with {} occurences", name.0, (name.1).0, (name.1).1);
+				println!("{}: {} ({}) with {} occurences", name.0, ((name.1).0).0, ((name.1).0).1, (name.1).1);
 			}
 			
 			println!();
 			
-			// --------------- Map --------------------
+			// --------------- Parallel map --------------------
+			
+			#[cfg(feature = "rayon")]
+			{
+				start_time = time::get_time();
+				let names = compute_most_frequent_city_parallel(&cities);
+				print_time("Listed city names by parallel map in ", "", time::get_time() - start_time);
+				
+				for name in names.iter().take(3).enumerate() {
+					println!("{}: {} with {} occurences", name.0, (name.1).0, (name.1).1);
+				}
+				
+				println!();
+			}
 			
-			let city_set = cities.clone();
+			// --------------- Streaming --------------------
+			
+			// The standard input can only be read once
+			if file != STDIN_FILE {
+				start_time = time::get_time();
+				match count_cities_in_file(&file, &ReadOptions::default(), None,
+						&CountOptions::default()) {
+					Ok(counts) => {
+						let mut names: Vec<(String, usize)> = counts.into_iter().collect();
+						sort_by_count(&mut names);
+						print_time("Counted city names while reading in ", "", time::get_time() - start_time);
+						
+						for name in names.iter().take(3).enumerate() {
+							println!("{}: {} with {} occurences", name.0, (name.1).0, (name.1).1);
+						}
+					},
+					Err(error) => {
+						println!("{}", error);
+					}
+				}
+				
+				println!();
+			}
+			
+			// --------------- Single pass --------------------
 			
 			start_time = time::get_time();
-			let names = compute_most_frequent_city_by_map(&city_set);
-			print_time("Listed city names by map in ", "", time::get_time() - start_time);
+			let name = most_frequent_city(&cities, &CountOptions::default());
+			print_time("Found most frequent city name in ", "", time::get_time() - start_time);
 			
-			for name in names.iter().take(3).enumerate() {
+			if let Some(name) = name {
+				println!("0: {} with {} occurences", name.0, name.1);
+			}
+			
+			println!();
+			
+			// --------------- Heap --------------------
+			
+			start_time = time::get_time();
+			let names = top_n_cities(&cities, 3, &CountOptions::default());
+			print_time("Listed top city names by heap in ", "", time::get_time() - start_time);
+			
+			for name in names.iter().enumerate() {
 				println!("{}: {} with {} occurences", name.0, (name.1).0, (name.1).1);
 			}
 			
 			println!();
 			
-			// At least once in germany
+			// At least once in the country
 			// --------------- List --------------------
 			
 			let city_set = cities.clone();
 			
 			start_time = time::get_time();
-			let names = compute_most_frequent_city_by_sorting_in_de(city_set);
-			print_time("Listed city names (de) by sorting in ", "", time::get_time() - start_time);
+			let names = compute_most_frequent_city_by_sorting_in_country(city_set, &country);
+			print_time(&format!("Listed city names ({}) by sorting in ", country), "",
+				time::get_time() - start_time);
 			
 			for name in names.iter().take(3).enumerate() {
 				println!("{}: {} with {} occurences", name.0, (name.1).0, (name.1).1);
@@ -104,8 +181,10 @@ fn main() {
 			let city_set = cities.clone();
 			
 			start_time = time::get_time();
-			let names = compute_most_frequent_city_by_map_in_de(&city_set);
-			print_time("Listed city names (de) by map in ", "", time::get_time() - start_time);
+			let names = compute_most_frequent_city_in_country(&city_set, &country,
+				&CountOptions::default());
+			print_time(&format!("Listed city names ({}) by map in ", country), "",
+				time::get_time() - start_time);
 			
 			for name in names.iter().take(3).enumerate() {
 				println!("{}: {} with {} occurences", name.0, (name.1).0, (name.1).1);
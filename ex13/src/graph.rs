@@ -1,17 +1,48 @@
+#[cfg(feature = "serde")]
+extern crate bincode;
+extern crate flate2;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(test)]
+extern crate serde_json;
 extern crate zip;
 
+use self::flate2::read::GzDecoder;
+#[cfg(feature = "rayon")]
+use self::rayon::prelude::*;
+#[cfg(feature = "serde")]
+use self::serde::{Deserialize, Serialize};
 use self::zip::ZipArchive;
 use self::zip::result::ZipError;
 
-use std::collections::{BinaryHeap, HashSet};
-use std::cmp::min;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::cmp::{min, Ordering, Reverse};
 use std::error::Error as StdError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Error as IOError};
+#[cfg(feature = "serde")]
+use std::io::BufWriter;
+#[cfg(test)]
+use std::env;
+#[cfg(test)]
+use std::io::{Cursor, Read, Write};
+use std::iter;
+use std::mem;
 use std::num::{ParseFloatError, ParseIntError};
-use std::result::Result;
+use std::result::Result as StdResult;
 use std::string::String;
+#[cfg(feature = "rayon")]
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+#[cfg(test)]
+use std::sync::Arc as SharedArc;
+#[cfg(test)]
+use std::thread;
+use std::f64;
+use std::u64;
+use std::usize;
 use std::vec::Vec;
 
 #[derive(Debug)]
@@ -20,7 +51,22 @@ pub enum Error {
 	IoError(IOError),
 	ParseIntError(ParseIntError),
 	ParseFloatError(ParseFloatError),
-	ZipError(ZipError)
+	ZipError(ZipError),
+	#[cfg(feature = "serde")]
+	BincodeError(bincode::Error)
+}
+
+/// Result type of the graph operations.
+pub type Result<T> = StdResult<T, Error>;
+
+impl Error {
+	
+	/// Create a format error with the given message.
+	pub fn format<S: Into<String>>(message: S) -> Error {
+		Error::FormatError {
+			message: message.into()
+		}
+	}
 }
 
 impl From<ParseIntError> for Error {
@@ -47,6 +93,13 @@ impl From<zip::result::ZipError> for Error {
     }
 }
 
+#[cfg(feature = "serde")]
+impl From<bincode::Error> for Error {
+	fn from(err: bincode::Error) -> Error {
+		Error::BincodeError(err)
+	}
+}
+
 impl<'a> From<&'a str> for Error {
 	fn from(err: &str) -> Error {
 		Error::FormatError {
@@ -63,6 +116,12 @@ impl From<String> for Error {
 	}
 }
 
+impl From<Box<dyn StdError>> for Error {
+	fn from(err: Box<dyn StdError>) -> Error {
+		Error::format(err.to_string())
+	}
+}
+
 impl Display for Error {
 	fn fmt(&self, f: &mut Formatter) -> FmtResult {
 		match *self {
@@ -70,45 +129,99 @@ impl Display for Error {
 			Error::ParseIntError(ref err) => write!(f, "Parse Error: {}", err),
 			Error::ParseFloatError(ref err) => write!(f, "Parse Error: {}", err),
 			Error::ZipError(ref err) => write!(f, "Zip Error: {}", err),
+			#[cfg(feature = "serde")]
+			Error::BincodeError(ref err) => write!(f, "Bincode Error: {}", err),
 			Error::FormatError { ref message } => write!(f, "Format error: {}", message)
 		}
 	}
 }
 
 impl StdError for Error {
-	fn description(&self) -> &str {
-		match *self {
-			Error::IoError(ref err) => err.description(),
-			Error::ParseIntError(ref err) => err.description(),
-			Error::ParseFloatError(ref err) => err.description(),
-			Error::ZipError(ref err) => err.description(),
-			Error::FormatError { ref message } => &message
-		}
-	}
-	
-	fn cause(&self) -> Option<&StdError> {
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
 		match *self {
 			Error::IoError(ref err) => Some(err),
 			Error::ParseIntError(ref err) => Some(err),
 			Error::ParseFloatError(ref err) => Some(err),
 			Error::ZipError(ref err) => Some(err),
+			#[cfg(feature = "serde")]
+			Error::BincodeError(ref err) => Some(err),
 			Error::FormatError { .. } => None
 		}
 	}
 }
 
 const MAPBB_NODE_DIVIDER: u64 = 15;
+const EARTH_RADIUS_METERS: f64 = 6371000.0;
+const PROGRESS_LINE_INTERVAL: usize = 100000;
+
+/// Max distance in meters of a coordinate to the node it is snapped to.
+pub const MAX_SNAP_DISTANCE: f64 = 1000.0;
+
+/// Assumed speed of a bicycle in km/h.
+pub const BICYCLE_SPEED: u64 = 15;
+/// Assumed walking speed in km/h.
+pub const PEDESTRIAN_SPEED: u64 = 5;
+
+/// Maximum speed of the arcs read from an edge list, which has no speed column.
+pub const DEFAULT_EDGE_LIST_MAX_SPEED: u64 = 50;
+
+/// Compute the travel time in seconds for the distance in meters with the
+/// speed in km/h, the time is infinite for a speed of zero.
+fn travel_time(distance: u64, speed: u64) -> f64 {
+	if speed == 0 {
+		return f64::INFINITY;
+	}
+	
+	(distance as f64) * 3.6 / speed as f64
+}
+
+/// Compute the great-circle distance in meters between two coordinates.
+pub fn haversine_meters(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+	let delta_lat = (lat2 - lat1).to_radians();
+	let delta_lon = (lon2 - lon1).to_radians();
+	
+	let a = (delta_lat / 2.0).sin().powi(2)
+		+ lat1.to_radians().cos() * lat2.to_radians().cos() * (delta_lon / 2.0).sin().powi(2);
+	
+	2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+/// Compute the great-circle distance in meters between the nodes of an arc.
+fn arc_length(nodes: &[Node], arc: &Arc) -> f64 {
+	let (tail_node, head_node) = (&nodes[arc.tail_node_id], &nodes[arc.head_node_id]);
+	haversine_meters(tail_node.latitude, tail_node.longitude, head_node.latitude, head_node.longitude)
+}
+
+/// Find the candidate node closest to the given coordinates.
+///
+/// The result is a tuple of the distance in meters and the node id.
+fn find_nearest_of<I: Iterator<Item = usize>>(nodes: &[Node], candidates: I,
+		latitude: f64, longitude: f64) -> Option<(f64, usize)> {
+	let mut nearest = None;
+	
+	for node_id in candidates {
+		let node = &nodes[node_id];
+		let distance = haversine_meters(latitude, longitude, node.latitude, node.longitude);
+		
+		match nearest {
+			Some((nearest_distance, _)) if nearest_distance <= distance => { },
+			_ => { nearest = Some((distance, node_id)); }
+		}
+	}
+	
+	nearest
+}
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 struct Node {
 	id: usize,
 	latitude: f64,
-	longitude: f64,
-	traceback_arc: Option<usize>,
-	settled: bool,
-	distance: Option<f64>
+	longitude: f64
 }
 
-struct Arc {
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Arc {
 	head_node_id: usize,
 	tail_node_id: usize,
 	distance: u64,
@@ -116,476 +229,5180 @@ struct Arc {
 	costs: f64
 }
 
+impl Arc {
+	
+	/// Returns the id of the node this arc starts at.
+	pub fn tail_node_id(&self) -> usize {
+		self.tail_node_id
+	}
+	
+	/// Returns the id of the node this arc ends at.
+	pub fn head_node_id(&self) -> usize {
+		self.head_node_id
+	}
+	
+	/// Returns the length of this arc in meters.
+	pub fn distance(&self) -> u64 {
+		self.distance
+	}
+	
+	/// Returns the max speed on this arc in km/h.
+	pub fn max_speed(&self) -> u64 {
+		self.max_speed
+	}
+	
+	/// Returns the current costs of this arc.
+	pub fn costs(&self) -> f64 {
+		self.costs
+	}
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Graph {
 	nodes: Box<Vec<Node>>,
-	adjacency_lists: Box<Vec<Vec<Arc>>>
+	adjacency_lists: Box<Vec<Vec<Arc>>>,
+	directed: bool,
+	/// Unit of the distances in the source file, distances are stored in meters.
+	distance_unit: DistanceUnit,
+	/// Node ids of the source file if it was read with sparse ids.
+	original_ids: Option<Vec<u64>>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	landmarks: Vec<Landmark>,
+	/// Search state of the methods which store their results in the graph.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	search_state: SearchState,
+	/// Search state of the backward search of compute_shortest_path_bidirectional.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	backward_search_state: SearchState,
+	/// Arcs entering each node of a directed graph as (tail node id, arc
+	/// index) pairs, None until the backward search needs them or after
+	/// the arcs changed.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	incoming_arcs: Option<Vec<Vec<(usize, usize)>>>
+}
+
+/// Scratch buffers of a shortest path search.
+///
+/// A search state can be reused for many searches. Each thread needs its
+/// own search state to run searches on a shared graph.
+#[derive(Clone, Debug, Default)]
+pub struct SearchState {
+	distances: Vec<Option<f64>>,
+	/// Tail node id and index of the arc in the tail's adjacency list.
+	traceback_arcs: Vec<Option<(usize, usize)>>,
+	settled: Vec<bool>
+}
+
+impl SearchState {
+	
+	/// Create a search state for the given graph.
+	pub fn new(graph: &Graph) -> SearchState {
+		let mut state = SearchState::default();
+		state.reset(graph.num_nodes());
+		
+		state
+	}
+	
+	/// Extend the buffers to the given number of nodes, the results of
+	/// the previous search are kept.
+	fn resize(&mut self, num_nodes: usize) {
+		self.distances.resize(num_nodes, None);
+		self.traceback_arcs.resize(num_nodes, None);
+		self.settled.resize(num_nodes, false);
+	}
+	
+	/// Clear the results of the previous search.
+	fn reset(&mut self, num_nodes: usize) {
+		self.distances.clear();
+		self.distances.resize(num_nodes, None);
+		self.traceback_arcs.clear();
+		self.traceback_arcs.resize(num_nodes, None);
+		self.settled.clear();
+		self.settled.resize(num_nodes, false);
+	}
+	
+	/// Returns the costs to the node or None if it was not reached.
+	pub fn distance(&self, node_id: usize) -> Option<f64> {
+		self.distances.get(node_id).cloned().unwrap_or(None)
+	}
+	
+	/// Returns true if the costs to the node are final.
+	pub fn is_settled(&self, node_id: usize) -> bool {
+		self.settled.get(node_id).cloned().unwrap_or(false)
+	}
+	
+	/// Returns the tail node id and the arc index of the arc leading to
+	/// the node on its shortest path.
+	fn traceback_arc(&self, node_id: usize) -> Option<(usize, usize)> {
+		self.traceback_arcs.get(node_id).cloned().unwrap_or(None)
+	}
+	
+	/// Collect the nodes on the traceback from the start node to the end node.
+	pub fn traceback_path(&self, end_node: usize) -> Vec<usize> {
+		let mut path = vec![end_node];
+		let mut node = end_node;
+		
+		while let Some((previous_node, _)) = self.traceback_arc(node) {
+			path.push(previous_node);
+			node = previous_node;
+		}
+		
+		path.reverse();
+		return path;
+	}
+	
+	/// Returns the costs and the nodes of the path to the end node.
+	pub fn shortest_path(&self, end_node: usize) -> Option<(f64, Vec<usize>)> {
+		self.distance(end_node).map(|distance| (distance, self.traceback_path(end_node)))
+	}
+}
+
+/// Costs as key of a priority queue.
+///
+/// NaN costs are rejected when the arc costs are set, the keys are compared
+/// with f64::total_cmp anyway, so a NaN can not break the queue.
+#[derive(Clone, Copy, Debug)]
+struct CostKey(f64);
+
+impl PartialEq for CostKey {
+	fn eq(&self, other: &CostKey) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for CostKey { }
+
+impl PartialOrd for CostKey {
+	fn partial_cmp(&self, other: &CostKey) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for CostKey {
+	fn cmp(&self, other: &CostKey) -> Ordering {
+		self.0.total_cmp(&other.0)
+	}
+}
+
+/// Min-heap of items ordered by their key, ties are broken by the items.
+///
+/// The keys of queued items can not be decreased, instead the item is
+/// pushed again and the outdated entry is skipped when popped (lazy deletion).
+struct MinQueue<K: Ord, T: Ord> {
+	heap: BinaryHeap<Reverse<(K, T)>>
+}
+
+impl<K: Ord + Copy, T: Ord> MinQueue<K, T> {
+	
+	fn new() -> MinQueue<K, T> {
+		MinQueue { heap: BinaryHeap::new() }
+	}
+	
+	fn push(&mut self, key: K, item: T) {
+		self.heap.push(Reverse((key, item)));
+	}
+	
+	/// Remove the item with the smallest key.
+	fn pop(&mut self) -> Option<(K, T)> {
+		self.heap.pop().map(|Reverse(entry)| entry)
+	}
+	
+	/// Returns the smallest key.
+	fn min_key(&self) -> Option<K> {
+		self.heap.peek().map(|&Reverse((key, _))| key)
+	}
+}
+
+/// Read access to the arcs of a graph representation.
+///
+/// The traversals below are shared by the adjacency lists of the graph
+/// and the compressed sparse row format.
+trait Adjacency {
+	
+	/// Returns the number of nodes.
+	fn node_count(&self) -> usize;
+	
+	/// Returns the arcs leaving the given node.
+	fn arcs_of(&self, node_id: usize) -> &[Arc];
+	
+	/// Returns an error if the node id is not part of this graph.
+	fn check_node_id(&self, node_id: usize) -> Result<()> {
+		if node_id >= self.node_count() {
+			return Err(Error::format(format!("Invalid node id {}! (graph has {} nodes)",
+				node_id, self.node_count())));
+		}
+		
+		Ok(())
+	}
+}
+
+/// Visit the nodes reachable from the start node in breadth-first order.
+///
+/// Nodes with a mark of '1' are skipped, all visited nodes are marked.
+/// The pending queue has to be empty and is empty again afterwards.
+fn visit_reachable<G: Adjacency, F: FnMut(usize)>(graph: &G, start_node: usize,
+		marked_nodes: &mut [u8], pending_nodes: &mut VecDeque<usize>, mut visit: F) {
+	// Nodes are marked when queued, thus each node is queued only once
+	pending_nodes.push_back(start_node);
+	marked_nodes[start_node] = 1;
+	
+	while let Some(node) = pending_nodes.pop_front() {
+		visit(node);
+		
+		for arc in graph.arcs_of(node) {
+			if marked_nodes[arc.head_node_id] == 0 {
+				marked_nodes[arc.head_node_id] = 1;
+				pending_nodes.push_back(arc.head_node_id);
+			}
+		}
+	}
+}
+
+/// See Graph::compute_reachable_nodes.
+fn reachable_nodes<G: Adjacency>(graph: &G, node_id: usize) -> (usize, Box<Vec<u8>>) {
+	let mut marked_nodes = box vec![0_u8; graph.node_count()];
+	let mut num_marked = 0;
+	
+	visit_reachable(graph, node_id, &mut marked_nodes, &mut VecDeque::new(), |_| num_marked += 1);
+	
+	return (num_marked, marked_nodes);
+}
+
+/// See Graph::compute_lcc.
+fn largest_reachable_set<G: Adjacency>(graph: &G) -> (usize, Box<Vec<usize>>) {
+	let node_count = graph.node_count();
+	
+	let mut unvisited_nodes = vec![0_u8; node_count];
+	let mut marked_nodes = vec![0_u8; node_count];
+	let mut pending_nodes = VecDeque::new();
+	let mut reachable_nodes = box Vec::<usize>::new();
+	let mut lcc = (0, box Vec::<usize>::new());
+	
+	for i in 0..node_count {
+		if unvisited_nodes[i] == 1 {
+			continue;
+		}
+		
+		reachable_nodes.clear();
+		visit_reachable(graph, i, &mut marked_nodes, &mut pending_nodes,
+			|node| reachable_nodes.push(node));
+		
+		// Only clear the marks of this search instead of the whole list
+		for &node in reachable_nodes.iter() {
+			unvisited_nodes[node] = 1;
+			marked_nodes[node] = 0;
+		}
+		
+		if reachable_nodes.len() > lcc.0 {
+			reachable_nodes.sort();
+			lcc = (reachable_nodes.len(), reachable_nodes.clone());
+		}
+	}
+	
+	return lcc;
+}
+
+/// See Graph::run_search, the heuristic receives the node id.
+fn run_search_on<G: Adjacency, F: Fn(usize) -> f64>(graph: &G, state: &mut SearchState,
+		start_nodes: &[usize], target_node: Option<usize>, max_distance: f64, heuristic: F) {
+	// Min-heap with entries of (distance + heuristic, node id)
+	let mut active_nodes = MinQueue::new();
+	
+	for &start_node in start_nodes {
+		let key = heuristic(start_node);
+		
+		state.distances[start_node] = Some(0.0);
+		active_nodes.push(CostKey(key), start_node);
+	}
+	
+	loop {
+		let res = active_nodes.pop();
+		match res {
+			None => { return; },
+			Some((CostKey(queued_key), node_id)) => {
+				let distance = state.distances[node_id].unwrap();
+				let key = distance + heuristic(node_id);
+				
+				// Node was already settled or the entry is outdated
+				// (lazy deletion of decreased keys)
+				if state.settled[node_id] || queued_key > key {
+					continue;
+				}
+				
+				// Settle active node
+				state.settled[node_id] = true;
+				
+				if target_node == Some(node_id) {
+					return;
+				}
+				
+				// Updated all connected nodes
+				for (arc_index, arc) in graph.arcs_of(node_id).iter().enumerate() {
+					if arc.head_node_id == node_id {
+						// A node connected to itself ...
+						// I love this dataset
+						continue;
+					}
+					
+					// Sums which overflow to infinity are treated as unreachable
+					let next_distance = distance + arc.costs;
+					if !next_distance.is_finite() || next_distance > max_distance {
+						continue;
+					}
+					
+					let next_node = arc.head_node_id;
+					
+					if !state.settled[next_node] {
+						match state.distances[next_node] {
+							None => { },
+							Some(distance) => {
+								if distance <= next_distance {
+									continue;
+								}
+							}
+						}
+						
+						state.distances[next_node] = Some(next_distance);
+						state.traceback_arcs[next_node] = Some((node_id, arc_index));
+						
+						let next_key = next_distance + heuristic(next_node);
+						active_nodes.push(CostKey(next_key), next_node);
+					}
+				}
+			}
+		}
+	}
+}
+
+/// Costs from and to a landmark node for the ALT heuristic, unreachable
+/// nodes have infinite costs.
+struct Landmark {
+	distances_from: Vec<f64>,
+	distances_to: Vec<f64>
+}
+
+/// Options for reading a graph from a file.
+#[derive(Clone, Copy, Debug)]
+pub struct GraphBuildOptions {
+	/// Insert only the tail->head arc instead of arcs in both directions.
+	pub directed: bool,
+	/// Unit of the arc distances in the graph file.
+	pub distance_unit: DistanceUnit,
+	/// Map arbitrary node ids of the graph file to the indices 0..n instead
+	/// of requiring the ids 0..n in order.
+	pub sparse_ids: bool,
+	/// Costs of the arcs after reading the graph.
+	pub cost_model: CostModel
+}
+
+impl Default for GraphBuildOptions {
+	fn default() -> GraphBuildOptions {
+		GraphBuildOptions {
+			directed: true,
+			distance_unit: DistanceUnit::Meters,
+			sparse_ids: false,
+			cost_model: CostModel::Distance
+		}
+	}
+}
+
+/// Arc costs of a graph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CostModel {
+	/// The costs are the distance in meters.
+	Distance,
+	/// The costs are the travel time in seconds with the given max vehicle
+	/// speed in km/h, limited by the max speed of the arcs.
+	TravelTime(u64)
+}
+
+/// Unit of the arc distances in a graph file.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceUnit {
+	Meters,
+	Kilometers,
+	/// Tenths of a meter.
+	Decimeters
+}
+
+impl DistanceUnit {
+	
+	/// Convert a distance in this unit to whole meters.
+	pub fn to_meters(&self, distance: f64) -> u64 {
+		match *self {
+			DistanceUnit::Meters => distance.round() as u64,
+			DistanceUnit::Kilometers => (distance * 1000.0).round() as u64,
+			DistanceUnit::Decimeters => (distance / 10.0).round() as u64
+		}
+	}
+}
+
+/// Routing profile for the travel time costs of the arcs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profile {
+	/// A car with the given max speed in km/h, limited by the max speed of the arcs.
+	Car(u64),
+	/// A bicycle with BICYCLE_SPEED on every arc.
+	Bicycle,
+	/// A pedestrian with PEDESTRIAN_SPEED on every arc.
+	Pedestrian
+}
+
+/// Summary of a path through a graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathSummary {
+	/// Sum of the arc distances in meters.
+	pub distance: f64,
+	/// Travel time in seconds.
+	pub travel_time: f64,
+	pub num_arcs: usize,
+	/// Number of nodes on the path (without source and target) with
+	/// a degree above 2.
+	pub num_turns: usize
+}
+
+/// Summary statistics of a graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphStats {
+	pub num_nodes: usize,
+	/// Number of stored arcs, an undirected edge counts twice.
+	pub num_arcs: usize,
+	pub num_components: usize,
+	pub largest_component_size: usize,
+	pub min_degree: usize,
+	pub max_degree: usize,
+	pub mean_degree: f64,
+	/// Sum of the arc distances in meters, an undirected edge counts once.
+	pub total_arc_length: u64
 }
 
 impl Graph {
 	
-	fn read_lines<R: BufRead>(&mut self, buf: R) -> Result<(), Error> {
+	/// Create an empty directed graph.
+	pub fn new() -> Graph {
+		Graph::with_options(GraphBuildOptions::default())
+	}
+	
+	/// Create an empty graph with the given options.
+	pub fn with_options(options: GraphBuildOptions) -> Graph {
+		Graph {
+			nodes: box Vec::new(),
+			adjacency_lists: box Vec::new(),
+			directed: options.directed,
+			distance_unit: options.distance_unit,
+			original_ids: if options.sparse_ids { Some(Vec::new()) } else { None },
+			landmarks: Vec::new(),
+			search_state: SearchState::default(),
+			backward_search_state: SearchState::default(),
+			incoming_arcs: None
+		}
+	}
+	
+	/// Add a node to this graph.
+	///
+	/// Nodes are numbered in the order of insertion, the result
+	/// is the id of the new node.
+	pub fn add_node(&mut self, latitude: f64, longitude: f64) -> usize {
+		let node_id = self.nodes.len();
+		
+		self.nodes.push(Node {
+			id: node_id,
+			latitude: latitude,
+			longitude: longitude
+		});
+		self.adjacency_lists.push(Vec::new());
+		self.incoming_arcs = None;
+		
+		node_id
+	}
+	
+	/// Add an arc between two existing nodes.
+	///
+	/// In an undirected graph the reverse arc is added as well.
+	pub fn add_arc(&mut self, tail_node: usize, head_node: usize, distance: u64,
+			max_speed: u64) -> Result<()> {
+		try!(self.check_node_id(tail_node));
+		try!(self.check_node_id(head_node));
+		
+		self.incoming_arcs = None;
+		self.adjacency_lists[tail_node].push(Arc {
+			tail_node_id: tail_node,
+			head_node_id: head_node,
+			distance: distance,
+			max_speed: max_speed,
+			costs: distance as f64
+		});
+		
+		// An undirected graph gets the reverse arc too
+		if !self.directed {
+			self.adjacency_lists[head_node].push(Arc {
+				tail_node_id: head_node,
+				head_node_id: tail_node,
+				distance: distance,
+				max_speed: max_speed,
+				costs: distance as f64
+			});
+		}
+		
+		Ok(())
+	}
+	
+	/// Read the graph from the lines of a text file.
+	///
+	/// Malformed arc lines are collected in the error list (if given) and
+	/// skipped instead of aborting. Malformed node lines always abort, since
+	/// a skipped node would shift the ids of the following nodes. The progress
+	/// callback (if given) receives the number of processed and expected lines
+	/// every PROGRESS_LINE_INTERVAL lines and at the end.
+	fn read_lines<'p, R: BufRead>(&mut self, buf: R, mut errors: Option<&mut Vec<Error>>,
+			mut progress: Option<&mut (dyn FnMut(usize, usize) + 'p)>) -> Result<()> {
 		let mut line_number = 0;
 		let mut total_line_number = 0;
 		
 		let mut node_count = 0;
 		let mut arc_count = 0;
 		
+		// Maps the node ids of the file to node indices in sparse mode
+		let mut id_map = self.original_ids.as_ref().map(|_| HashMap::new());
+		
 		for line_res in buf.lines() {
 			let line = try!(line_res);
-			let line = line.trim();
+			let mut line = line.trim();
 			
 			total_line_number += 1;
 			
+			// Files exported on Windows might start with a byte order mark
+			if total_line_number == 1 {
+				line = line.trim_start_matches('\u{feff}');
+			}
+			
 			if line.starts_with("#") || line.is_empty() {
 				continue;
 			}
 			
 			line_number += 1;
 			
-			let parts: Vec<&str> = line.split(' ').collect();
+			let parts: Vec<&str> = line.split_whitespace().collect();
 			if parts.len() < 1 {
-				return Err(Error::from(format!("Invalid graph file! (general, line {})", total_line_number)));
+				return Err(Error::format(format!("Invalid graph file! (general, line {})", total_line_number)));
 			}
 			
 			match line_number {
 				1 => {
 					node_count = try!(parts[0].parse::<usize>());
 					self.nodes.reserve_exact(node_count);
+					self.adjacency_lists.reserve_exact(node_count);
+					if let Some(ref mut id_map) = id_map {
+						id_map.reserve(node_count);
+					}
 				},
 				2 => {
 					arc_count = try!(parts[0].parse::<usize>());
-					self.adjacency_lists.reserve_exact(arc_count);
-					
-					for _ in 0..arc_count {
-						self.adjacency_lists.push(Vec::new());
-					}
+				},
+				_ if line_number < node_count + 3 => {
+					try!(self.read_node_line(&parts, line_number - 3, id_map.as_mut(), total_line_number));
 				},
 				_ => {
-					if line_number < node_count + 3 {
-						if parts.len() != 3 {
-							return Err(Error::from(format!("Invalid graph file! (Invalid node, line {})", total_line_number)));
-						}
-						
-						let node_id = try!(parts[0].parse::<usize>());
-						let latitude = try!(parts[1].parse::<f64>());
-						let longitude = try!(parts[2].parse::<f64>());
-						
-						self.nodes.push(Node {
-							id: node_id,
-							latitude: latitude,
-							longitude: longitude,
-							traceback_arc: None,
-							settled: false,
-							distance: None
-						});
- 					} else if line_number < node_count + arc_count + 3 {
-						if parts.len() != 4 {
-							return Err(Error::from(format!("Invalid graph file! (Invalid arc, line {})", total_line_number)));
-						}
-						
-						let tail_node = try!(parts[0].parse::<usize>());
-						let head_node = try!(parts[1].parse::<usize>());
-						
-						let distance = try!(parts[2].parse::<u64>());
-						let max_speed = try!(parts[3].parse::<u64>());
-						
-						self.adjacency_lists[tail_node].push(Arc {
-							tail_node_id: tail_node,
-							head_node_id: head_node,
-							distance: distance,
-							max_speed: max_speed,
-							costs: distance as f64
-						});
-						
-						// We create an undirected graph
-						/*self.adjacency_lists[head_node].push(Arc {
-							tail_node_id: head_node,
-							head_node_id: tail_node,
-							distance: distance,
-							max_speed: max_speed,
-							costs: distance as f64
-						});*/
+					let res = if line_number < node_count + arc_count + 3 {
+						self.read_arc_line(&parts, id_map.as_ref(), total_line_number)
  					} else {
-	 					return Err(Error::from(format!("Invalid graph file! (Additional lines, line {})", total_line_number)));
- 					}
+	 					Err(Error::format(format!("Invalid graph file! (Additional lines, line {})", total_line_number)))
+ 					};
+					
+					match (res, errors.as_mut()) {
+						(Err(err), Some(errors)) => { errors.push(err); },
+						(Err(err), None) => { return Err(err); },
+						(Ok(_), _) => { }
+					}
 				}
 			}
+			
+			if line_number % PROGRESS_LINE_INTERVAL == 0 {
+				if let Some(ref mut progress) = progress {
+					progress(line_number, node_count + arc_count + 2);
+				}
+			}
+		}
+		
+		if let Some(ref mut progress) = progress {
+			progress(line_number, node_count + arc_count + 2);
+		}
+		
+		// A truncated file misses nodes or arcs of the header counts
+		if line_number < node_count + arc_count + 2 {
+			let num_node_lines = min(line_number.saturating_sub(2), node_count);
+			let num_arc_lines = line_number.saturating_sub(node_count + 2);
+			let err = Error::format(format!("Invalid graph file! (Expected {} nodes and {} arcs, found {} nodes and {} arcs)",
+				node_count, arc_count, num_node_lines, num_arc_lines));
+			
+			match errors {
+				Some(errors) => { errors.push(err); },
+				None => { return Err(err); }
+			}
 		}
 		
 		Ok(())
 	}
 	
-	pub fn read_graph_from_file(name: &str) -> Result<Graph, Error> {
-		let file = try!(File::open(name));
-		let mut archive = try!(ZipArchive::new(file));
+	/// Read a node line, the declared id has to match the position of the
+	/// line in the node section.
+	///
+	/// With an id map (sparse ids) the declared id only has to be unique
+	/// and is mapped to the index of the new node.
+	fn read_node_line(&mut self, parts: &[&str], expected_id: usize,
+			id_map: Option<&mut HashMap<u64, usize>>, total_line_number: usize) -> Result<()> {
+		if parts.len() != 3 {
+			return Err(Error::format(format!("Invalid graph file! (Invalid node, line {})", total_line_number)));
+		}
 		
-		let mut graph = Graph {
-			nodes: box Vec::new(),
-			adjacency_lists: box Vec::new()
-		};
+		if let Some(id_map) = id_map {
+			let node_id = try!(parts[0].parse::<u64>());
+			let latitude = try!(parts[1].parse::<f64>());
+			let longitude = try!(parts[2].parse::<f64>());
+			
+			if id_map.contains_key(&node_id) {
+				return Err(Error::format(format!("Invalid graph file! (Duplicate node id {}, line {})",
+					node_id, total_line_number)));
+			}
+			
+			let index = self.add_node(latitude, longitude);
+			id_map.insert(node_id, index);
+			if let Some(ref mut original_ids) = self.original_ids {
+				original_ids.push(node_id);
+			}
+			return Ok(());
+		}
 		
-		for index in 0 .. archive.len() {
-			let entry = try!(archive.by_index(index));
-			let buf = BufReader::new(entry);
-			try!(graph.read_lines(buf));
+		let node_id = try!(parts[0].parse::<usize>());
+		if node_id < expected_id {
+			return Err(Error::format(format!("Invalid graph file! (Duplicate node id {}, line {})",
+				node_id, total_line_number)));
+		} else if node_id != expected_id {
+			return Err(Error::format(format!("Invalid graph file! (Unexpected node id {}, line {})",
+				node_id, total_line_number)));
 		}
 		
-		Ok(graph)
+		let latitude = try!(parts[1].parse::<f64>());
+		let longitude = try!(parts[2].parse::<f64>());
+		
+		self.add_node(latitude, longitude);
+		Ok(())
 	}
 	
-	/// Set arc costs to travel time in whole seconds.
-	pub fn set_arc_costs_to_travel_time(&mut self, max_vehicle_speed: u64) {
-		for arcs in self.adjacency_lists.iter_mut() {
-			for arc in arcs.iter_mut() {
-				// Compute travel time in whole seconds
-				arc.costs = (arc.distance as f64) * 3.6 / min(arc.max_speed, max_vehicle_speed) as f64;
-			}
+	fn read_arc_line(&mut self, parts: &[&str], id_map: Option<&HashMap<u64, usize>>,
+			total_line_number: usize) -> Result<()> {
+		if parts.len() != 4 {
+			return Err(Error::format(format!("Invalid graph file! (Invalid arc, line {})", total_line_number)));
 		}
-	}
-	
-	/// Set arc costs to distance.
-	pub fn set_arc_costs_to_distance(&mut self) {
-		for arcs in self.adjacency_lists.iter_mut() {
-			for arc in arcs.iter_mut() {
-				arc.costs = arc.distance as f64;
+		
+		let (tail_node, head_node) = match id_map {
+			Some(id_map) => {
+				let tail_id = try!(parts[0].parse::<u64>());
+				let head_id = try!(parts[1].parse::<u64>());
+				match (id_map.get(&tail_id), id_map.get(&head_id)) {
+					(Some(&tail_node), Some(&head_node)) => (tail_node, head_node),
+					_ => {
+						return Err(Error::format(format!("Invalid graph file! (Invalid arc node, line {})", total_line_number)));
+					}
+				}
+			},
+			None => (try!(parts[0].parse::<usize>()), try!(parts[1].parse::<usize>()))
+		};
+		
+		// Meters are integral, other units may have a fractional part
+		let distance = match self.distance_unit {
+			DistanceUnit::Meters => try!(parts[2].parse::<u64>()),
+			unit => {
+				let distance = try!(parts[2].parse::<f64>());
+				if !distance.is_finite() || distance < 0.0 {
+					return Err(Error::format(format!("Invalid graph file! (Invalid arc distance, line {})", total_line_number)));
+				}
+				unit.to_meters(distance)
 			}
+		};
+		let max_speed = try!(parts[3].parse::<u64>());
+		
+		if tail_node >= self.num_nodes() || head_node >= self.num_nodes() {
+			return Err(Error::format(format!("Invalid graph file! (Invalid arc node, line {})", total_line_number)));
 		}
+		
+		self.add_arc(tail_node, head_node, distance, max_speed)
 	}
 	
-	/// Returns the number of nodes in this graph.
-	pub fn num_nodes(&self) -> usize {
-		self.nodes.len()
+	/// Read a graph in the plain text format from the given reader.
+	pub fn read_graph_from_reader<R: BufRead>(reader: R) -> Result<Graph> {
+		Graph::read_graph_from_reader_with_options(reader, GraphBuildOptions::default())
 	}
 	
-	/// Return the number of arcs in this graph.
-	pub fn num_arcs(&self) -> usize {
-		self.adjacency_lists.len()
+	/// Read a graph in the plain text format from the given reader with options.
+	pub fn read_graph_from_reader_with_options<R: BufRead>(reader: R,
+			options: GraphBuildOptions) -> Result<Graph> {
+		let mut graph = Graph::with_options(options);
+		try!(graph.read_lines(reader, None, None));
+		graph.set_arc_costs(options.cost_model);
+		
+		Ok(graph)
 	}
 	
-	/// Compute all reachable nodes from the given start node.
+	/// Read a graph in the plain text format from a string.
+	pub fn from_str_data(data: &str) -> Result<Graph> {
+		Graph::read_graph_from_reader(data.as_bytes())
+	}
+	
+	/// Read a graph in the plain text format from the given reader and skip
+	/// malformed arc lines.
 	///
-	/// The result is a tuple of the number selected nodes and an 'mark' list
-	/// where a value of '1' represents a visited node. 
-	fn compute_reachable_nodes(&self, node_id: usize) -> (usize, Box<Vec<u8>>) {
-		let mut marked_nodes = box vec![0_u8; self.num_nodes()];
-		let mut num_marked = 0;
+	/// The result contains the graph and the errors of the skipped lines.
+	pub fn read_graph_from_reader_lenient<R: BufRead>(reader: R)
+			-> Result<(Graph, Vec<Error>)> {
+		let mut graph = Graph::new();
+		let mut errors = Vec::new();
+		try!(graph.read_lines(reader, Some(&mut errors), None));
+		
+		Ok((graph, errors))
+	}
+	
+	/// Read a graph from an uncompressed text file.
+	pub fn read_graph_from_text_file(name: &str) -> Result<Graph> {
+		Graph::read_graph_from_text_file_with_options(name, GraphBuildOptions::default())
+	}
+	
+	/// Read a graph from an uncompressed text file with options.
+	pub fn read_graph_from_text_file_with_options(name: &str, options: GraphBuildOptions)
+			-> Result<Graph> {
+		let file = try!(File::open(name));
+		Graph::read_graph_from_reader_with_options(BufReader::new(file), options)
+	}
+	
+	pub fn read_graph_from_file(name: &str) -> Result<Graph> {
+		Graph::read_graph_from_file_with_options(name, GraphBuildOptions::default())
+	}
+	
+	/// Read a graph from a zip or gzip (.gz) file, the default options
+	/// create a directed graph.
+	pub fn read_graph_from_file_with_options(name: &str, options: GraphBuildOptions)
+			-> Result<Graph> {
+		Graph::read_compressed_file(name, options, None, None)
+	}
+	
+	/// Read a graph from a zip or gzip (.gz) file and report the progress.
+	///
+	/// The progress callback is called periodically with the number of
+	/// processed lines and the total number of lines given by the node and
+	/// arc count of the header, comments and empty lines are not counted.
+	pub fn read_graph_from_file_with_progress(name: &str,
+			progress: &mut dyn FnMut(usize, usize)) -> Result<Graph> {
+		Graph::read_compressed_file(name, GraphBuildOptions::default(), None, Some(progress))
+	}
+	
+	/// Read a graph from a zip or gzip (.gz) file and skip malformed
+	/// arc lines.
+	///
+	/// The result contains the graph and the errors of the skipped lines.
+	pub fn read_graph_lenient(name: &str) -> Result<(Graph, Vec<Error>)> {
+		let mut errors = Vec::new();
+		let graph = try!(Graph::read_compressed_file(name, GraphBuildOptions::default(),
+			Some(&mut errors), None));
+		
+		Ok((graph, errors))
+	}
+	
+	/// Write this graph in the bincode format to the given file.
+	#[cfg(feature = "serde")]
+	pub fn save_bincode(&self, name: &str) -> Result<()> {
+		let file = try!(File::create(name));
+		try!(bincode::serialize_into(BufWriter::new(file), self));
 		
-		let mut pending_nodes = box HashSet::<usize>::new();
-		pending_nodes.insert(node_id);
+		Ok(())
+	}
+	
+	/// Read a graph in the bincode format from the given file.
+	#[cfg(feature = "serde")]
+	pub fn load_bincode(name: &str) -> Result<Graph> {
+		let file = try!(File::open(name));
+		Ok(try!(bincode::deserialize_from(BufReader::new(file))))
+	}
+	
+	/// Read a graph from a `tail,head,weight` edge list without header.
+	///
+	/// The node count is one past the largest node id and the weight is used
+	/// as distance of the arc. All nodes are placed at (0, 0), use
+	/// read_coordinates_from_csv to read their coordinates.
+	pub fn read_graph_from_edge_list<R: BufRead>(reader: R, max_speed: u64) -> Result<Graph> {
+		let mut arcs = Vec::new();
+		let mut node_count = 0;
 		
-		while !pending_nodes.is_empty() {
-			let mut next_nodes = box HashSet::<usize>::new();
+		for (index, line_res) in reader.lines().enumerate() {
+			let line = try!(line_res);
+			let line = line.trim();
+			if line.is_empty() {
+				continue;
+			}
 			
-			for node in pending_nodes.drain() {
-				if marked_nodes[node] == 1 {
-					continue;
-				}
-				
-				marked_nodes[node] = 1;
-				num_marked += 1;
-				
-				for arc in self.adjacency_lists[node].iter() {
-					if marked_nodes[arc.head_node_id] == 0 {
-						next_nodes.insert(arc.head_node_id);
-					}
-				}
+			let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+			if parts.len() != 3 {
+				return Err(Error::format(format!("Invalid edge list! (line {})", index + 1)));
 			}
 			
-			pending_nodes = next_nodes;
+			let tail_node = try!(parts[0].parse::<usize>());
+			let head_node = try!(parts[1].parse::<usize>());
+			let weight = try!(parts[2].parse::<u64>());
+			
+			node_count = node_count.max(tail_node + 1).max(head_node + 1);
+			arcs.push((tail_node, head_node, weight));
 		}
 		
-		return (num_marked, marked_nodes);
+		let mut graph = Graph::new();
+		graph.nodes.reserve(node_count);
+		for _ in 0..node_count {
+			graph.add_node(0.0, 0.0);
+		}
+		
+		for (tail_node, head_node, weight) in arcs {
+			try!(graph.add_arc(tail_node, head_node, weight, max_speed));
+		}
+		
+		Ok(graph)
 	}
 	
-	/// Mark all nodes in the largest connected component.
+	/// Read the node coordinates from a `node,latitude,longitude` CSV.
 	///
-	/// The result is a tuple comprising of the number of nodes in the
-	/// lcc and the list of nodes contained in it.
-	pub fn compute_lcc(&self) -> (usize, Box<Vec<usize>>){
-		let node_count = self.num_nodes();
-		
-		let mut unvisited_nodes = box vec![0_u8; node_count];
-		let mut marked_nodes = box Vec::<usize>::new();
-		let mut lcc = (0, box Vec::<usize>::new());
-		
-		for i in 0..node_count {
-			if unvisited_nodes[i] == 1 {
+	/// Nodes which are not listed keep their previous coordinates.
+	pub fn read_coordinates_from_csv<R: BufRead>(&mut self, reader: R) -> Result<()> {
+		for (index, line_res) in reader.lines().enumerate() {
+			let line = try!(line_res);
+			let line = line.trim();
+			if line.is_empty() {
 				continue;
 			}
 			
-			let (num_marked, reachable_nodes) = self.compute_reachable_nodes(i);
-			if num_marked == 0 {
-				continue;
+			let parts: Vec<&str> = line.split(',').map(|part| part.trim()).collect();
+			if parts.len() != 3 {
+				return Err(Error::format(format!("Invalid coordinate list! (line {})", index + 1)));
 			}
 			
-			marked_nodes.clear();
-			for j in 0..node_count {
-				if reachable_nodes[j] == 0 {
-					continue;
-				}
-				if j > i {
-					unvisited_nodes[j] = 1;
-				}
-
-				if num_marked > lcc.0 {
-					marked_nodes.push(i);
-				}
+			let node_id = try!(parts[0].parse::<usize>());
+			if node_id >= self.nodes.len() {
+				return Err(Error::format(format!("Invalid coordinate list! (Invalid node, line {})", index + 1)));
 			}
 			
-			if num_marked > lcc.0 {
-				lcc = (num_marked, marked_nodes.clone());
-			}
+			self.nodes[node_id].latitude = try!(parts[1].parse::<f64>());
+			self.nodes[node_id].longitude = try!(parts[2].parse::<f64>());
 		}
 		
-		return lcc;
+		Ok(())
 	}
 	
-	/// Compute the shortest paths for a given start node.
+	/// Read a graph in the DIMACS challenge format.
 	///
-	/// Compute the shortest paths from the given start node
-	/// using Dijkstra's algorithm.
-	pub fn compute_shortest_paths(&mut self, start_node: usize) {
-		self.nodes[start_node].distance = Some(0.0);
+	/// The arcs are read from the .gr file and the node coordinates from the
+	/// .co file. DIMACS node ids start at 1, the arc weights are used as
+	/// distance and the arcs have no speed limit.
+	pub fn read_graph_from_dimacs(gr_name: &str, co_name: &str) -> Result<Graph> {
+		let gr_file = BufReader::new(try!(File::open(gr_name)));
+		let co_file = BufReader::new(try!(File::open(co_name)));
 		
-		let mut active_nodes = BinaryHeap::<(i64, usize)>::new();
-		active_nodes.push((0, start_node));
+		Graph::read_dimacs(gr_file, co_file)
+	}
+	
+	fn read_dimacs<G: BufRead, C: BufRead>(gr_buf: G, co_buf: C) -> Result<Graph> {
+		let mut coordinates: Vec<Option<(f64, f64)>> = Vec::new();
 		
-		loop {
-			let res = active_nodes.pop();
-			match res {
-				None => { return; },
-				Some(node_index) => {
-					let (start, tmp) = self.nodes.split_at_mut(node_index.1);
-					let (node, end) = tmp.split_first_mut().unwrap();
-					
-					// Node was already settled
-					if node.settled {
-						continue;
+		for (index, line_res) in co_buf.lines().enumerate() {
+			let line = try!(line_res);
+			let parts: Vec<&str> = line.split_whitespace().collect();
+			
+			match parts.first() {
+				Some(&"p") if parts.len() == 5 => {
+					coordinates = vec![None; try!(parts[4].parse::<usize>())];
+				},
+				Some(&"v") if parts.len() == 4 => {
+					let node_id = try!(parts[1].parse::<usize>());
+					if node_id == 0 || node_id > coordinates.len() {
+						return Err(Error::format(format!("Invalid coordinate file! (Invalid node, line {})", index + 1)));
 					}
 					
-					// Settle active node
-					node.settled = true;
-					
-					// Updated all connected nodes
-					for arc in self.adjacency_lists[node.id].iter() {
-						if arc.head_node_id == node_index.1 {
-							// A node connected to itself ...
-							// I love this dataset
-							continue;
-						}
-						
-						let next_distance = node.distance.unwrap() + arc.costs;
-						let next_node;
-						
-						if arc.head_node_id < node_index.1 {
-							next_node = &mut start[arc.head_node_id];
-						} else {
-							next_node = &mut end[arc.head_node_id - node_index.1 - 1];
-						}
-						
-						if !next_node.settled {
-							match next_node.distance {
-								None => { },
-								Some(distance) => {
-									if distance < next_distance {
-										continue;
-									}
-								}
-							}
-							
-							next_node.distance = Some(next_distance);
-							next_node.traceback_arc = Some(node_index.1);
-							
-							// Use negative numbers since we only have a max-heap
-							active_nodes.push((-(next_distance * 1000.0) as i64, next_node.id));
-						}
-					}
+					// Coordinates are given in millionths of a degree
+					let longitude = try!(parts[2].parse::<i64>()) as f64 / 1e6;
+					let latitude = try!(parts[3].parse::<i64>()) as f64 / 1e6;
+					coordinates[node_id - 1] = Some((latitude, longitude));
+				},
+				None | Some(&"c") => { },
+				_ => {
+					return Err(Error::format(format!("Invalid coordinate file! (line {})", index + 1)));
 				}
 			}
 		}
-	}
-	
-	/// Compute distance and travel time of the selected path.
-	pub fn travel_to(&self, end_node: usize, max_speed: u64) -> (f64, f64) {
-		let mut node = &self.nodes[end_node];
-		let mut time = 0.0;
-		let mut distance = 0.0;
 		
-		loop {
-			let arc_ref = node.traceback_arc;
-			match arc_ref {
+		let mut graph = Graph::new();
+		for (node_id, coordinate) in coordinates.iter().enumerate() {
+			match *coordinate {
+				Some((latitude, longitude)) => { graph.add_node(latitude, longitude); },
 				None => {
-					break;
-				},
-				Some(arc_index) => {
-					// Just use some default arc
-					let mut arc = &self.adjacency_lists[arc_index][0];
-					for _arc in self.adjacency_lists[arc_index].iter() {
-						if _arc.head_node_id == node.id {
-							arc = _arc;
-							break;
-						}
+					return Err(Error::format(format!("Invalid coordinate file! (Missing node {})", node_id + 1)));
+				}
+			}
+		}
+		
+		for (index, line_res) in gr_buf.lines().enumerate() {
+			let line = try!(line_res);
+			let parts: Vec<&str> = line.split_whitespace().collect();
+			
+			match parts.first() {
+				Some(&"p") if parts.len() == 4 => {
+					if try!(parts[2].parse::<usize>()) != graph.num_nodes() {
+						return Err(Error::format(format!("Invalid graph file! (Node count differs, line {})", index + 1)));
 					}
+				},
+				Some(&"a") if parts.len() == 4 => {
+					let tail_node = try!(parts[1].parse::<usize>());
+					let head_node = try!(parts[2].parse::<usize>());
+					let weight = try!(parts[3].parse::<u64>());
 					
-					distance += arc.distance as f64;
-					
-					// s = v * t => t = s / v
-					// t = [s] = s / v = [m] / [m/s] = [m] / ([km/h] / 3.6)
-					time += arc.distance as f64 * 3.6 / min(max_speed, arc.max_speed) as f64;
+					if tail_node == 0 || head_node == 0 {
+						return Err(Error::format(format!("Invalid graph file! (Invalid arc node, line {})", index + 1)));
+					}
 					
-					// Follow to previous node
-					node = &self.nodes[arc.tail_node_id];
+					try!(graph.add_arc(tail_node - 1, head_node - 1, weight, u64::MAX));
+				},
+				None | Some(&"c") => { },
+				_ => {
+					return Err(Error::format(format!("Invalid graph file! (line {})", index + 1)));
 				}
 			}
 		}
 		
-		return (distance, time);
+		Ok(graph)
 	}
 	
-	/// Resets this graph to enable a different Dijkstra calculation.
-	pub fn reset(&mut self) {
-		for node in self.nodes.iter_mut() {
-			node.traceback_arc = None;
-			node.settled = false;
-			node.distance = None;
+	fn read_compressed_file(name: &str, options: GraphBuildOptions,
+			mut errors: Option<&mut Vec<Error>>,
+			mut progress: Option<&mut dyn FnMut(usize, usize)>) -> Result<Graph> {
+		let file = try!(File::open(name));
+		let mut graph = Graph::with_options(options);
+		
+		if name.ends_with(".gz") {
+			let buf = BufReader::new(GzDecoder::new(file));
+			try!(graph.read_lines(buf, errors, progress));
+			graph.set_arc_costs(options.cost_model);
+			return Ok(graph);
+		}
+		
+		let mut archive = try!(ZipArchive::new(file));
+		
+		for index in 0 .. archive.len() {
+			let entry = try!(archive.by_index(index));
+			let buf = BufReader::new(entry);
+			try!(graph.read_lines(buf, errors.as_mut().map(|errors| &mut **errors),
+				progress.as_mut().map(|progress| &mut **progress)));
 		}
+		
+		graph.set_arc_costs(options.cost_model);
+		Ok(graph)
 	}
 	
-	/// Returns the furthes node from the selected start
-	pub fn get_furthest_node(&self) -> (f64, usize) {
-		let mut max_dist = (0.0, 0);
+	/// Set the arc costs according to the given cost model.
+	pub fn set_arc_costs(&mut self, cost_model: CostModel) {
+		match cost_model {
+			CostModel::Distance => self.set_arc_costs_to_distance(),
+			CostModel::TravelTime(max_vehicle_speed) => self.set_arc_costs_to_travel_time(max_vehicle_speed)
+		}
+	}
+	
+	/// Set arc costs to travel time in whole seconds.
+	///
+	/// Arcs with a max speed of zero can not be passed and get infinite costs.
+	pub fn set_arc_costs_to_travel_time(&mut self, max_vehicle_speed: u64) {
+		for arcs in self.adjacency_lists.iter_mut() {
+			for arc in arcs.iter_mut() {
+				arc.costs = travel_time(arc.distance, min(arc.max_speed, max_vehicle_speed));
+			}
+		}
+	}
+	
+	/// Set arc costs to the travel time in seconds of the given profile.
+	///
+	/// Bicycles and pedestrians have a fixed speed which does not depend
+	/// on the max speed of the arcs.
+	pub fn set_costs_for_profile(&mut self, profile: Profile) {
+		let speed = match profile {
+			Profile::Car(max_vehicle_speed) => {
+				self.set_arc_costs_to_travel_time(max_vehicle_speed);
+				return;
+			},
+			Profile::Bicycle => BICYCLE_SPEED,
+			Profile::Pedestrian => PEDESTRIAN_SPEED
+		};
 		
-		for node in self.nodes.iter() {
-			match node.distance {
-				None => { },
-				Some(dist) => {
-					if max_dist.0 < dist {
-						max_dist = (dist, node.id);
-					}
-				}
+		for arcs in self.adjacency_lists.iter_mut() {
+			for arc in arcs.iter_mut() {
+				arc.costs = travel_time(arc.distance, speed);
+			}
+		}
+	}
+	
+	/// Set arc costs to distance.
+	pub fn set_arc_costs_to_distance(&mut self) {
+		for arcs in self.adjacency_lists.iter_mut() {
+			for arc in arcs.iter_mut() {
+				arc.costs = arc.distance as f64;
+			}
+		}
+	}
+	
+	/// Set arc costs to the result of the given cost function.
+	///
+	/// An error is returned and the costs are not changed if the cost
+	/// function returns negative or NaN costs for any arc, since the shortest
+	/// path computation requires non-negative costs.
+	pub fn set_arc_costs_with<F: Fn(&Arc) -> f64>(&mut self, cost_function: F) -> Result<()> {
+		for arc in self.adjacency_lists.iter().flat_map(|arcs| arcs.iter()) {
+			let costs = cost_function(arc);
+			if costs.is_nan() || costs < 0.0 {
+				return Err(Error::format(format!("Invalid costs {} of the arc from node {} to node {}!",
+					costs, arc.tail_node_id, arc.head_node_id)));
 			}
 		}
 		
-		return max_dist;
+		for arcs in self.adjacency_lists.iter_mut() {
+			for arc in arcs.iter_mut() {
+				arc.costs = cost_function(arc);
+			}
+		}
+		
+		Ok(())
 	}
 	
-	pub fn generate_mapbb(&self, str: &mut String, end_node: usize) {
-		let mut node = &self.nodes[end_node];
-		let mut node_count = 0;
+	/// Set the distance of every arc to the great-circle distance between its nodes.
+	///
+	/// The distances are rounded to whole meters, the arc costs are not
+	/// changed and have to be set again afterwards.
+	pub fn recompute_distances_from_coordinates(&mut self) {
+		let nodes = &self.nodes;
 		
-		loop {
-			if node_count % MAPBB_NODE_DIVIDER == 0 {
-				if node_count != 0 {
-					str.push_str(" ");
+		for arcs in self.adjacency_lists.iter_mut() {
+			for arc in arcs.iter_mut() {
+				arc.distance = arc_length(nodes, arc).round() as u64;
+			}
+		}
+	}
+	
+	/// Find all arcs whose distance differs from the great-circle
+	/// distance between its nodes by more than the tolerance in meters.
+	///
+	/// The result contains the tail and head node id of each arc.
+	pub fn validate_distances(&self, tolerance: f64) -> Vec<(usize, usize)> {
+		let mut invalid_arcs = Vec::new();
+		
+		for arcs in self.adjacency_lists.iter() {
+			for arc in arcs.iter() {
+				if (arc.distance as f64 - arc_length(&self.nodes, arc)).abs() > tolerance {
+					invalid_arcs.push((arc.tail_node_id, arc.head_node_id));
 				}
-				
-				str.push_str(format!("{:.4},{:.4}", node.latitude, node.longitude).as_str());
 			}
+		}
+		
+		return invalid_arcs;
+	}
+	
+	/// Replace parallel arcs between the same nodes by the arc with the
+	/// lowest costs.
+	///
+	/// The remaining arc keeps the position of the first parallel arc.
+	pub fn deduplicate_arcs(&mut self) {
+		let mut positions = vec![usize::MAX; self.num_nodes()];
+		self.incoming_arcs = None;
+		
+		for arcs in self.adjacency_lists.iter_mut() {
+			let mut unique_arcs: Vec<Arc> = Vec::with_capacity(arcs.len());
 			
-			node_count += 1;
-			
-			let arc_ref = node.traceback_arc;
-			match arc_ref {
-				None => {
-					break;
-				},
-				Some(arc_index) => {
-					// Just use some default arc
-					let mut arc = &self.adjacency_lists[arc_index][0];
-					for _arc in self.adjacency_lists[arc_index].iter() {
-						if _arc.head_node_id == node.id {
-							arc = _arc;
-							break;
-						}
-					}
-					
-					// Follow to previous node
-					node = &self.nodes[arc.tail_node_id];
+			for arc in arcs.drain(..) {
+				let position = positions[arc.head_node_id];
+				if position == usize::MAX {
+					positions[arc.head_node_id] = unique_arcs.len();
+					unique_arcs.push(arc);
+				} else if arc.costs < unique_arcs[position].costs {
+					unique_arcs[position] = arc;
 				}
 			}
+			
+			for arc in unique_arcs.iter() {
+				positions[arc.head_node_id] = usize::MAX;
+			}
+			*arcs = unique_arcs;
 		}
-
-		// Append last node
-		if node_count % MAPBB_NODE_DIVIDER != 0 {
-			str.push_str(format!("{:.4},{:.4}", node.latitude, node.longitude).as_str());
+	}
+	
+	/// Remove all arcs from a node to itself.
+	///
+	/// The result is the number of removed self-loops. An undirected graph
+	/// stores each self-loop twice, both copies count as one self-loop.
+	pub fn remove_self_loops(&mut self) -> usize {
+		let mut num_removed = 0;
+		self.incoming_arcs = None;
+		
+		for arcs in self.adjacency_lists.iter_mut() {
+			let length = arcs.len();
+			arcs.retain(|arc| arc.tail_node_id != arc.head_node_id);
+			num_removed += length - arcs.len();
 		}
+		
+		if self.directed { num_removed } else { num_removed / 2 }
 	}
-}
-
-impl Display for Graph {
-	fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
-		try!(write!(fmt, "["));
-		for (i, node) in self.nodes.iter().enumerate() {
-			let lst = &self.adjacency_lists[node.id];
+	
+	/// Returns the ids of all nodes without incoming and outgoing arcs.
+	pub fn isolated_nodes(&self) -> Vec<usize> {
+		let mut has_arcs = vec![false; self.num_nodes()];
+		
+		for arcs in self.adjacency_lists.iter() {
+			for arc in arcs.iter() {
+				has_arcs[arc.tail_node_id] = true;
+				has_arcs[arc.head_node_id] = true;
+			}
+		}
+		
+		(0..self.num_nodes()).filter(|&node_id| !has_arcs[node_id]).collect()
+	}
+	
+	/// Remove all nodes without incoming and outgoing arcs.
+	///
+	/// The remaining nodes are renumbered to 0..n in their previous order,
+	/// precomputed landmarks are dropped. The result is the number of
+	/// removed nodes.
+	pub fn remove_isolated_nodes(&mut self) -> usize {
+		let isolated_nodes = self.isolated_nodes();
+		if isolated_nodes.is_empty() {
+			return 0;
+		}
+		
+		// Map the old node ids to the new ones
+		let mut new_ids: Vec<Option<usize>> = (0..self.num_nodes()).map(Some).collect();
+		for &node_id in isolated_nodes.iter() {
+			new_ids[node_id] = None;
+		}
+		let mut next_id = 0;
+		for new_id in new_ids.iter_mut() {
+			if new_id.is_some() {
+				*new_id = Some(next_id);
+				next_id += 1;
+			}
+		}
+		
+		let nodes = mem::replace(&mut *self.nodes, Vec::with_capacity(next_id));
+		let adjacency_lists = mem::replace(&mut *self.adjacency_lists, Vec::with_capacity(next_id));
+		
+		for (mut node, mut arcs) in nodes.into_iter().zip(adjacency_lists) {
+			let new_id = match new_ids[node.id] {
+				Some(new_id) => new_id,
+				None => { continue; }
+			};
 			
-			for (j, adj) in lst.iter().enumerate() {
-				try!(write!(fmt, "{}->{}({})", adj.tail_node_id, adj.head_node_id, adj.costs));
+			for arc in arcs.iter_mut() {
+				arc.tail_node_id = new_id;
+				arc.head_node_id = new_ids[arc.head_node_id].unwrap();
+			}
+			
+			node.id = new_id;
+			self.nodes.push(node);
+			self.adjacency_lists.push(arcs);
+		}
+		
+		if let Some(ref mut original_ids) = self.original_ids {
+			let mut node_id = 0;
+			original_ids.retain(|_| {
+				node_id += 1;
+				new_ids[node_id - 1].is_some()
+			});
+		}
+		
+		self.landmarks.clear();
+		self.search_state = SearchState::default();
+		self.incoming_arcs = None;
+		
+		isolated_nodes.len()
+	}
+	
+	/// Returns true if the arcs of this graph are directed.
+	pub fn is_directed(&self) -> bool {
+		self.directed
+	}
+	
+	/// Returns the unit of the distances in the file this graph was read from.
+	///
+	/// The distances of the arcs are always stored in meters.
+	pub fn distance_unit(&self) -> DistanceUnit {
+		self.distance_unit
+	}
+	
+	/// Returns the number of nodes in this graph.
+	pub fn num_nodes(&self) -> usize {
+		self.nodes.len()
+	}
+	
+	/// Return the number of arcs in this graph.
+	pub fn num_arcs(&self) -> usize {
+		self.adjacency_lists.iter().map(|arcs| arcs.len()).sum()
+	}
+	
+	/// Returns the node id in the source file of the given node.
+	///
+	/// Without sparse ids the node id equals the index of the node.
+	pub fn original_id(&self, node_id: usize) -> Option<u64> {
+		match self.original_ids {
+			Some(ref original_ids) => original_ids.get(node_id).cloned(),
+			None => self.nodes.get(node_id).map(|node| node.id as u64)
+		}
+	}
+	
+	/// Returns the latitude and longitude of the given node.
+	pub fn node_coordinates(&self, node_id: usize) -> Option<(f64, f64)> {
+		self.nodes.get(node_id).map(|node| (node.latitude, node.longitude))
+	}
+	
+	/// Returns an iterator over the id, latitude and longitude of all nodes.
+	pub fn iter_nodes<'a>(&'a self) -> impl Iterator<Item = (usize, f64, f64)> + 'a {
+		self.nodes.iter().map(|node| (node.id, node.latitude, node.longitude))
+	}
+	
+	/// Returns an iterator over the tail node id, head node id, distance and
+	/// max speed of all arcs.
+	///
+	/// An undirected graph stores each edge as two arcs, both are returned.
+	pub fn iter_arcs<'a>(&'a self) -> impl Iterator<Item = (usize, usize, u64, u64)> + 'a {
+		self.adjacency_lists.iter().flat_map(|arcs| arcs.iter())
+			.map(|arc| (arc.tail_node_id, arc.head_node_id, arc.distance, arc.max_speed))
+	}
+	
+	/// Returns an iterator over the head node id and costs of all arcs
+	/// leaving the given node.
+	pub fn neighbors<'a>(&'a self, node_id: usize)
+			-> Result<impl Iterator<Item = (usize, f64)> + 'a> {
+		try!(self.check_node_id(node_id));
+		
+		Ok(self.adjacency_lists[node_id].iter().map(|arc| (arc.head_node_id, arc.costs)))
+	}
+	
+	/// Returns the number of arcs leaving the given node.
+	pub fn degree(&self, node_id: usize) -> Result<usize> {
+		try!(self.check_node_id(node_id));
+		
+		Ok(self.adjacency_lists[node_id].len())
+	}
+	
+	/// Returns the minimal costs of the arcs from the tail to the head node.
+	///
+	/// The result is None if there is no such arc or a node does not exist.
+	pub fn arc_cost(&self, tail_node: usize, head_node: usize) -> Option<f64> {
+		self.cheapest_arc(tail_node, head_node).map(|arc| arc.costs)
+	}
+	
+	fn cheapest_arc(&self, tail_node: usize, head_node: usize) -> Option<&Arc> {
+		let mut cheapest_arc: Option<&Arc> = None;
+		
+		for arc in self.adjacency_lists.get(tail_node).into_iter().flat_map(|arcs| arcs.iter()) {
+			if arc.head_node_id == head_node && cheapest_arc.map_or(true, |best| arc.costs < best.costs) {
+				cheapest_arc = Some(arc);
+			}
+		}
+		
+		cheapest_arc
+	}
+	
+	/// Returns the matrix of the minimal arc costs between all nodes.
+	///
+	/// The entry is None if there is no arc between the nodes and the
+	/// diagonal is zero. Fails if the graph has more than max_nodes nodes.
+	pub fn to_adjacency_matrix(&self, max_nodes: usize) -> Result<Vec<Vec<Option<f64>>>> {
+		if self.num_nodes() > max_nodes {
+			return Err(Error::format(format!("Graph too large for an adjacency matrix! ({} nodes, at most {})",
+				self.num_nodes(), max_nodes)));
+		}
+		
+		let mut matrix = vec![vec![None; self.num_nodes()]; self.num_nodes()];
+		
+		for (tail_node, arcs) in self.adjacency_lists.iter().enumerate() {
+			for arc in arcs.iter() {
+				let entry = &mut matrix[tail_node][arc.head_node_id];
+				if entry.map_or(true, |costs| arc.costs < costs) {
+					*entry = Some(arc.costs);
+				}
+			}
+		}
+		
+		for node_id in 0 .. self.num_nodes() {
+			matrix[node_id][node_id] = Some(0.0);
+		}
+		
+		Ok(matrix)
+	}
+	
+	/// Returns the matrix telling whether node j is reachable from node i.
+	///
+	/// Every node reaches itself. Fails if the graph has more than max_nodes
+	/// nodes.
+	pub fn transitive_closure(&self, max_nodes: usize) -> Result<Vec<Vec<bool>>> {
+		if self.num_nodes() > max_nodes {
+			return Err(Error::format(format!("Graph too large for a transitive closure! ({} nodes, at most {})",
+				self.num_nodes(), max_nodes)));
+		}
+		
+		let mut closure = vec![vec![false; self.num_nodes()]; self.num_nodes()];
+		
+		for (source, reachable) in closure.iter_mut().enumerate() {
+			for node_id in self.bfs(source) {
+				reachable[node_id] = true;
+			}
+		}
+		
+		Ok(closure)
+	}
+	
+	/// Returns the sum of the costs of the arcs leaving the given node.
+	///
+	/// In an undirected graph these are the costs of all incident edges.
+	pub fn weighted_degree(&self, node_id: usize) -> Result<f64> {
+		try!(self.check_node_id(node_id));
+		
+		Ok(self.adjacency_lists[node_id].iter().map(|arc| arc.costs).sum())
+	}
+	
+	/// Returns the sum of the arc costs, an undirected edge counts once.
+	pub fn total_graph_cost(&self) -> f64 {
+		self.edge_arcs().iter().map(|arc| arc.costs).sum()
+	}
+	
+	/// Returns an iterator over the nodes reachable from the source node
+	/// in breadth-first order, starting with the source node itself.
+	///
+	/// The iterator is empty if the source node does not exist.
+	pub fn bfs<'a>(&'a self, source: usize) -> impl Iterator<Item = usize> + 'a {
+		let mut visited_nodes = vec![false; self.num_nodes()];
+		let mut pending_nodes = VecDeque::new();
+		
+		if source < self.num_nodes() {
+			visited_nodes[source] = true;
+			pending_nodes.push_back(source);
+		}
+		
+		iter::from_fn(move || {
+			let node_id = match pending_nodes.pop_front() {
+				Some(node_id) => node_id,
+				None => { return None; }
+			};
+			for arc in self.adjacency_lists[node_id].iter() {
+				if !visited_nodes[arc.head_node_id] {
+					visited_nodes[arc.head_node_id] = true;
+					pending_nodes.push_back(arc.head_node_id);
+				}
+			}
+			
+			Some(node_id)
+		})
+	}
+	
+	/// Compute the discovery and finish times of a depth-first search from
+	/// the source node.
+	///
+	/// Both times share one counter which starts at 0 and is incremented at
+	/// each discovery and each finish. Nodes which are not reachable from the
+	/// source node have no times. The search uses an explicit stack. An error
+	/// is returned if the source node does not exist.
+	pub fn dfs_times(&self, source: usize) -> Result<(Vec<Option<usize>>, Vec<Option<usize>>)> {
+		try!(self.check_node_id(source));
+		
+		let mut discovery_times = vec![None; self.num_nodes()];
+		let mut finish_times = vec![None; self.num_nodes()];
+		let mut time = 0;
+		
+		// Node id and index of the next arc to follow
+		let mut stack: Vec<(usize, usize)> = vec![(source, 0)];
+		discovery_times[source] = Some(time);
+		time += 1;
+		
+		while let Some(&mut (node_id, ref mut arc_index)) = stack.last_mut() {
+			let arcs = &self.adjacency_lists[node_id];
+			
+			// Skip arcs to already discovered nodes
+			while *arc_index < arcs.len() && discovery_times[arcs[*arc_index].head_node_id].is_some() {
+				*arc_index += 1;
+			}
+			
+			if *arc_index < arcs.len() {
+				let head_node_id = arcs[*arc_index].head_node_id;
+				*arc_index += 1;
+				
+				discovery_times[head_node_id] = Some(time);
+				time += 1;
+				stack.push((head_node_id, 0));
+			} else {
+				finish_times[node_id] = Some(time);
+				time += 1;
+				stack.pop();
+			}
+		}
+		
+		Ok((discovery_times, finish_times))
+	}
+	
+	/// Sort the nodes so that every arc leads from an earlier to a later node.
+	///
+	/// A format error is returned if the graph contains a cycle. In an
+	/// undirected graph every edge is a cycle of its two arcs.
+	pub fn topological_sort(&self) -> Result<Vec<usize>> {
+		let mut in_degrees = vec![0; self.num_nodes()];
+		for arcs in self.adjacency_lists.iter() {
+			for arc in arcs.iter() {
+				in_degrees[arc.head_node_id] += 1;
+			}
+		}
+		
+		let mut pending_nodes: VecDeque<usize> = (0..self.num_nodes())
+			.filter(|&node_id| in_degrees[node_id] == 0)
+			.collect();
+		let mut order = Vec::with_capacity(self.num_nodes());
+		
+		while let Some(node_id) = pending_nodes.pop_front() {
+			order.push(node_id);
+			
+			for arc in self.adjacency_lists[node_id].iter() {
+				in_degrees[arc.head_node_id] -= 1;
+				if in_degrees[arc.head_node_id] == 0 {
+					pending_nodes.push_back(arc.head_node_id);
+				}
+			}
+		}
+		
+		// The nodes on a cycle never lose all of their incoming arcs
+		if order.len() < self.num_nodes() {
+			return Err(Error::format(format!("Graph contains a cycle! ({} nodes not sorted)",
+				self.num_nodes() - order.len())));
+		}
+		
+		Ok(order)
+	}
+	
+	/// Returns the number of nodes for each degree.
+	///
+	/// The degree of a node is the number of arcs leaving it. The result
+	/// contains (degree, node count) tuples sorted by degree, degrees
+	/// without nodes are omitted.
+	pub fn degree_histogram(&self) -> Vec<(usize, usize)> {
+		let max_degree = self.adjacency_lists.iter().map(|arcs| arcs.len()).max().unwrap_or(0);
+		let mut counts = vec![0; max_degree + 1];
+		
+		for arcs in self.adjacency_lists.iter() {
+			counts[arcs.len()] += 1;
+		}
+		
+		counts.into_iter().enumerate().filter(|&(_, count)| count > 0).collect()
+	}
+	
+	/// Compute the PageRank score of each node.
+	///
+	/// The arcs are the links between the nodes, thus each edge of an
+	/// undirected graph links in both directions. The score of nodes without
+	/// arcs is distributed uniformly over all nodes. The scores sum to 1.
+	pub fn pagerank(&self, damping: f64, iterations: usize) -> Vec<f64> {
+		let node_count = self.num_nodes();
+		if node_count == 0 {
+			return Vec::new();
+		}
+		
+		let mut scores = vec![1.0 / node_count as f64; node_count];
+		
+		for _ in 0..iterations {
+			let dangling_score: f64 = (0..node_count)
+				.filter(|&node_id| self.adjacency_lists[node_id].is_empty())
+				.map(|node_id| scores[node_id]).sum();
+			let base_score = ((1.0 - damping) + damping * dangling_score) / node_count as f64;
+			
+			let mut next_scores = vec![base_score; node_count];
+			for (node_id, arcs) in self.adjacency_lists.iter().enumerate() {
+				let share = damping * scores[node_id] / arcs.len() as f64;
+				for arc in arcs.iter() {
+					next_scores[arc.head_node_id] += share;
+				}
+			}
+			
+			scores = next_scores;
+		}
+		
+		// Remove rounding errors of the iterations
+		let total: f64 = scores.iter().sum();
+		scores.iter().map(|score| score / total).collect()
+	}
+	
+	/// Color the nodes with the greedy Welsh-Powell algorithm.
+	///
+	/// The nodes are processed in order of descending degree and get the
+	/// smallest color which is not used by any of their neighbors. The graph
+	/// should be undirected. The result contains the color of each node.
+	pub fn greedy_coloring(&self) -> Vec<usize> {
+		let mut order: Vec<usize> = (0..self.num_nodes()).collect();
+		order.sort_by_key(|&node_id| Reverse(self.adjacency_lists[node_id].len()));
+		
+		let mut colors = vec![usize::MAX; self.num_nodes()];
+		let mut used_colors = Vec::new();
+		
+		for node_id in order {
+			used_colors.clear();
+			used_colors.resize(self.adjacency_lists[node_id].len() + 1, false);
+			
+			// A node with n neighbors always gets one of the first n + 1 colors
+			for arc in self.adjacency_lists[node_id].iter() {
+				let color = colors[arc.head_node_id];
+				if arc.head_node_id != node_id && color < used_colors.len() {
+					used_colors[color] = true;
+				}
+			}
+			
+			colors[node_id] = used_colors.iter().position(|&used| !used).unwrap();
+		}
+		
+		return colors;
+	}
+	
+	/// Returns the number of colors used by greedy_coloring, which is an
+	/// upper bound of the chromatic number.
+	pub fn chromatic_number_upper_bound(&self) -> usize {
+		self.greedy_coloring().iter().max().map_or(0, |color| color + 1)
+	}
+	
+	/// Returns true if the nodes can be split into two sets such that
+	/// every arc connects nodes of different sets.
+	pub fn is_bipartite(&self) -> bool {
+		self.two_coloring().is_some()
+	}
+	
+	/// Color the nodes with the colors 0 and 1 such that no arc connects
+	/// nodes of the same color.
+	///
+	/// Each component is colored by a breadth-first search starting at its
+	/// smallest node with color 0. The graph should be undirected. The result
+	/// is None if the graph is not bipartite.
+	pub fn two_coloring(&self) -> Option<Vec<u8>> {
+		let mut colors = vec![u8::MAX; self.num_nodes()];
+		let mut pending_nodes = VecDeque::new();
+		
+		for root in 0..self.num_nodes() {
+			if colors[root] != u8::MAX {
+				continue;
+			}
+			
+			colors[root] = 0;
+			pending_nodes.push_back(root);
+			
+			while let Some(node_id) = pending_nodes.pop_front() {
+				for arc in self.adjacency_lists[node_id].iter() {
+					if colors[arc.head_node_id] == u8::MAX {
+						colors[arc.head_node_id] = 1 - colors[node_id];
+						pending_nodes.push_back(arc.head_node_id);
+					} else if colors[arc.head_node_id] == colors[node_id] {
+						return None;
+					}
+				}
+			}
+		}
+		
+		Some(colors)
+	}
+	
+	/// Compute the summary statistics of this graph.
+	///
+	/// The components are computed as for compute_components, thus the
+	/// graph should be undirected.
+	pub fn stats(&self) -> GraphStats {
+		let components = self.compute_components();
+		let num_components = components.iter().max().map_or(0, |component| component + 1);
+		
+		let mut component_sizes = vec![0; num_components];
+		for &component in components.iter() {
+			component_sizes[component] += 1;
+		}
+		
+		let degrees = self.adjacency_lists.iter().map(|arcs| arcs.len());
+		let mean_degree = match self.num_nodes() {
+			0 => 0.0,
+			num_nodes => self.num_arcs() as f64 / num_nodes as f64
+		};
+		
+		GraphStats {
+			num_nodes: self.num_nodes(),
+			num_arcs: self.num_arcs(),
+			num_components: num_components,
+			largest_component_size: component_sizes.iter().cloned().max().unwrap_or(0),
+			min_degree: degrees.clone().min().unwrap_or(0),
+			max_degree: degrees.max().unwrap_or(0),
+			mean_degree: mean_degree,
+			total_arc_length: self.edge_arcs().iter().map(|arc| arc.distance).sum()
+		}
+	}
+	
+	/// Compute all reachable nodes from the given start node.
+	///
+	/// The result is a tuple of the number selected nodes and an 'mark' list
+	/// where a value of '1' represents a visited node. 
+	fn compute_reachable_nodes(&self, node_id: usize) -> (usize, Box<Vec<u8>>) {
+		reachable_nodes(self, node_id)
+	}
+	
+	/// Mark all nodes in the largest connected component.
+	///
+	/// The result is a tuple comprising of the number of nodes in the
+	/// lcc and the sorted list of nodes contained in it. The nodes are
+	/// searched in order and nodes reached by an earlier search do not
+	/// start a search, thus for directed graphs the result is the largest
+	/// set of nodes reachable from one of these start nodes.
+	pub fn compute_lcc(&self) -> (usize, Box<Vec<usize>>){
+		largest_reachable_set(self)
+	}
+	
+	/// Label all nodes with the id of their connected component.
+	///
+	/// The result contains the component id for each node, ids are assigned
+	/// in the order of the smallest node of each component. The components
+	/// are computed by reachability, thus the graph should be undirected.
+	pub fn compute_components(&self) -> Vec<usize> {
+		let node_count = self.num_nodes();
+		
+		let mut components = vec![usize::MAX; node_count];
+		let mut num_components = 0;
+		
+		// Nodes are labeled when queued, thus each node is queued only once
+		let mut pending_nodes = VecDeque::new();
+		
+		for i in 0..node_count {
+			if components[i] != usize::MAX {
+				continue;
+			}
+			
+			components[i] = num_components;
+			pending_nodes.push_back(i);
+			
+			while let Some(node) = pending_nodes.pop_front() {
+				for arc in self.adjacency_lists[node].iter() {
+					if components[arc.head_node_id] == usize::MAX {
+						components[arc.head_node_id] = num_components;
+						pending_nodes.push_back(arc.head_node_id);
+					}
+				}
+			}
+			
+			num_components += 1;
+		}
+		
+		return components;
+	}
+	
+	/// Label all nodes with the id of their connected component using
+	/// multiple threads.
+	///
+	/// Every node is used as seed of a breadth-first search in parallel, a
+	/// search only continues at nodes it marks first. Searches meeting at an
+	/// arc belong to the same component, their seeds are merged afterwards.
+	/// The graph has to be undirected, the result is the same as for
+	/// compute_components.
+	#[cfg(feature = "rayon")]
+	pub fn compute_components_parallel(&self) -> Vec<usize> {
+		let node_count = self.num_nodes();
+		
+		// Seed of the search which marked each node
+		let owners: Vec<AtomicUsize> = (0..node_count).map(|_| AtomicUsize::new(usize::MAX)).collect();
+		
+		let merged_seeds: Vec<(usize, usize)> = (0..node_count).into_par_iter().flat_map_iter(|seed| {
+			let mut merged_seeds = Vec::new();
+			if owners[seed].compare_exchange(usize::MAX, seed, AtomicOrdering::Relaxed,
+					AtomicOrdering::Relaxed).is_err() {
+				return merged_seeds;
+			}
+			
+			let mut pending_nodes = VecDeque::new();
+			pending_nodes.push_back(seed);
+			
+			while let Some(node_id) = pending_nodes.pop_front() {
+				for arc in self.adjacency_lists[node_id].iter() {
+					match owners[arc.head_node_id].compare_exchange(usize::MAX, seed,
+							AtomicOrdering::Relaxed, AtomicOrdering::Relaxed) {
+						Ok(_) => { pending_nodes.push_back(arc.head_node_id); },
+						Err(owner) => {
+							if owner != seed {
+								merged_seeds.push((seed, owner));
+							}
+						}
+					}
+				}
+			}
+			
+			merged_seeds
+		}).collect();
+		
+		let mut sets = UnionFind::new(node_count);
+		for (seed, other_seed) in merged_seeds {
+			sets.union(seed, other_seed);
+		}
+		
+		// Number the components in the order of their smallest node
+		let mut component_ids = vec![usize::MAX; node_count];
+		let mut num_components = 0;
+		let mut components = Vec::with_capacity(node_count);
+		
+		for owner in owners.iter() {
+			let root = sets.find(owner.load(AtomicOrdering::Relaxed));
+			if component_ids[root] == usize::MAX {
+				component_ids[root] = num_components;
+				num_components += 1;
+			}
+			components.push(component_ids[root]);
+		}
+		
+		return components;
+	}
+	
+	/// Returns the number of connected components.
+	pub fn num_components(&self) -> usize {
+		self.compute_components().iter().max().map_or(0, |component| component + 1)
+	}
+	
+	/// Compute the strongly connected components of a directed graph.
+	///
+	/// The result contains the component id for each node. Tarjan's algorithm
+	/// completes the components in reverse topological order, thus a component
+	/// only has arcs to components with smaller ids. The depth-first search
+	/// uses an explicit stack to support deep graphs.
+	pub fn strongly_connected_components(&self) -> Vec<usize> {
+		let node_count = self.num_nodes();
+		
+		let mut components = vec![usize::MAX; node_count];
+		let mut num_components = 0;
+		let mut indices = vec![usize::MAX; node_count];
+		let mut low_links = vec![0; node_count];
+		let mut on_stack = vec![false; node_count];
+		let mut stack = Vec::new();
+		let mut next_index = 0;
+		
+		for root in 0..node_count {
+			if indices[root] != usize::MAX {
+				continue;
+			}
+			
+			// Entries of (node id, index of the next arc to visit)
+			let mut call_stack = vec![(root, 0)];
+			indices[root] = next_index;
+			low_links[root] = next_index;
+			next_index += 1;
+			stack.push(root);
+			on_stack[root] = true;
+			
+			while let Some(&mut (node_id, ref mut arc_index)) = call_stack.last_mut() {
+				if *arc_index < self.adjacency_lists[node_id].len() {
+					let next_node = self.adjacency_lists[node_id][*arc_index].head_node_id;
+					*arc_index += 1;
+					
+					if indices[next_node] == usize::MAX {
+						indices[next_node] = next_index;
+						low_links[next_node] = next_index;
+						next_index += 1;
+						stack.push(next_node);
+						on_stack[next_node] = true;
+						call_stack.push((next_node, 0));
+					} else if on_stack[next_node] {
+						low_links[node_id] = min(low_links[node_id], indices[next_node]);
+					}
+					continue;
+				}
+				
+				call_stack.pop();
+				if let Some(&(parent_node, _)) = call_stack.last() {
+					low_links[parent_node] = min(low_links[parent_node], low_links[node_id]);
+				}
+				
+				// The node is the root of a component
+				if low_links[node_id] == indices[node_id] {
+					loop {
+						let member = stack.pop().unwrap();
+						on_stack[member] = false;
+						components[member] = num_components;
+						if member == node_id {
+							break;
+						}
+					}
+					num_components += 1;
+				}
+			}
+		}
+		
+		return components;
+	}
+	
+	/// Returns the nodes whose removal disconnects their component.
+	///
+	/// The graph should be undirected, the result is sorted by node id.
+	pub fn articulation_points(&self) -> Vec<usize> {
+		self.compute_cut_elements().0
+	}
+	
+	/// Returns the edges whose removal disconnects their component.
+	///
+	/// The graph should be undirected, the result contains (smaller node id,
+	/// larger node id) tuples in sorted order.
+	pub fn bridges(&self) -> Vec<(usize, usize)> {
+		self.compute_cut_elements().1
+	}
+	
+	/// Compute the articulation points and bridges with a depth-first search
+	/// tracking the discovery and low values of the nodes.
+	fn compute_cut_elements(&self) -> (Vec<usize>, Vec<(usize, usize)>) {
+		let node_count = self.num_nodes();
+		
+		let mut discovery = vec![usize::MAX; node_count];
+		let mut low = vec![0; node_count];
+		let mut is_articulation_point = vec![false; node_count];
+		let mut bridges = Vec::new();
+		let mut next_index = 0;
+		
+		for root in 0..node_count {
+			if discovery[root] != usize::MAX {
+				continue;
+			}
+			
+			// Entries of (node id, parent node id, index of the next arc to visit,
+			// skipped arc to the parent). Only the mirror of the tree arc is
+			// skipped, parallel arcs to the parent are back edges.
+			let mut call_stack = vec![(root, usize::MAX, 0, false)];
+			let mut root_children = 0;
+			discovery[root] = next_index;
+			low[root] = next_index;
+			next_index += 1;
+			
+			while let Some(&mut (node_id, parent_node, ref mut arc_index, ref mut skipped_parent))
+					= call_stack.last_mut() {
+				if *arc_index < self.adjacency_lists[node_id].len() {
+					let next_node = self.adjacency_lists[node_id][*arc_index].head_node_id;
+					*arc_index += 1;
+					
+					if next_node == parent_node && !*skipped_parent {
+						*skipped_parent = true;
+					} else if discovery[next_node] == usize::MAX {
+						discovery[next_node] = next_index;
+						low[next_node] = next_index;
+						next_index += 1;
+						call_stack.push((next_node, node_id, 0, false));
+					} else {
+						low[node_id] = min(low[node_id], discovery[next_node]);
+					}
+					continue;
+				}
+				
+				call_stack.pop();
+				if parent_node == usize::MAX {
+					continue;
+				}
+				
+				low[parent_node] = min(low[parent_node], low[node_id]);
+				if low[node_id] > discovery[parent_node] {
+					bridges.push((min(parent_node, node_id), parent_node.max(node_id)));
+				}
+				if parent_node == root {
+					root_children += 1;
+				} else if low[node_id] >= discovery[parent_node] {
+					is_articulation_point[parent_node] = true;
+				}
+			}
+			
+			is_articulation_point[root] = root_children > 1;
+		}
+		
+		bridges.sort();
+		let points = (0..node_count).filter(|&node_id| is_articulation_point[node_id]).collect();
+		
+		(points, bridges)
+	}
+	
+	/// Compute the shortest paths for a given start node.
+	///
+	/// Compute the shortest paths from the given start node
+	/// using Dijkstra's algorithm.
+	pub fn compute_shortest_paths(&mut self, start_node: usize) {
+		self.run_dijkstra(start_node, None);
+	}
+	
+	/// Compute the shortest paths from the start node into the given
+	/// search state.
+	///
+	/// The graph is not modified, thus searches with their own search
+	/// state can run in parallel on a shared graph.
+	pub fn compute_shortest_paths_with_state(&self, state: &mut SearchState, start_node: usize)
+			-> Result<()> {
+		try!(self.check_node_id(start_node));
+		
+		state.reset(self.num_nodes());
+		self.run_search(state, &[start_node], None, f64::INFINITY, |_| 0.0);
+		
+		Ok(())
+	}
+	
+	/// Compute the shortest path between the source and the target node
+	/// with the given search state.
+	///
+	/// The result is the same as for compute_shortest_path, the graph is
+	/// not modified.
+	pub fn compute_shortest_path_with_state(&self, state: &mut SearchState, source: usize,
+			target: usize) -> Result<Option<(f64, Vec<usize>)>> {
+		try!(self.check_node_id(source));
+		try!(self.check_node_id(target));
+		
+		state.reset(self.num_nodes());
+		self.run_search(state, &[source], Some(target), f64::INFINITY, |_| 0.0);
+		
+		Ok(state.shortest_path(target))
+	}
+	
+	/// Compute the shortest path between the source and the target node.
+	///
+	/// The result is a tuple of the path costs and the list of nodes
+	/// from source to target or None if the target is not reachable.
+	pub fn compute_shortest_path(&mut self, source: usize, target: usize)
+			-> Result<Option<(f64, Vec<usize>)>> {
+		try!(self.check_node_id(source));
+		try!(self.check_node_id(target));
+		
+		self.reset();
+		self.run_dijkstra(source, Some(target));
+		
+		Ok(self.get_shortest_path(target))
+	}
+	
+	/// Compute the shortest path between the nodes closest to the given
+	/// latitude and longitude pairs.
+	///
+	/// A format error is returned if there is no node within
+	/// MAX_SNAP_DISTANCE of a coordinate. The result is the same as for
+	/// compute_shortest_path.
+	pub fn route_between_coordinates(&mut self, from: (f64, f64), to: (f64, f64))
+			-> Result<Option<(f64, Vec<usize>)>> {
+		let source = try!(self.snap_to_node(from));
+		let target = try!(self.snap_to_node(to));
+		
+		self.compute_shortest_path(source, target)
+	}
+	
+	fn snap_to_node(&self, (latitude, longitude): (f64, f64)) -> Result<usize> {
+		match find_nearest_of(&self.nodes, 0..self.num_nodes(), latitude, longitude) {
+			Some((distance, node_id)) if distance <= MAX_SNAP_DISTANCE => Ok(node_id),
+			_ => Err(Error::format(format!("No node within {} m of ({}, {})",
+				MAX_SNAP_DISTANCE, latitude, longitude)))
+		}
+	}
+	
+	/// Compute the shortest path between the source and the target node
+	/// using an A* search.
+	///
+	/// The great-circle distance to the target is used as heuristic. When the
+	/// arc costs are set to travel time it is divided by the max vehicle speed,
+	/// when the costs are set to distance a max vehicle speed of u64::MAX has to
+	/// be passed. The result is the same as for compute_shortest_path, a max
+	/// vehicle speed of zero is rejected with a format error.
+	pub fn compute_shortest_path_astar(&mut self, source: usize, target: usize,
+			max_vehicle_speed: u64) -> Result<Option<(f64, Vec<usize>)>> {
+		try!(self.check_node_id(source));
+		try!(self.check_node_id(target));
+		if max_vehicle_speed == 0 {
+			return Err(Error::format("The max vehicle speed of an A* search must not be zero!"));
+		}
+		
+		let target_latitude = self.nodes[target].latitude;
+		let target_longitude = self.nodes[target].longitude;
+		
+		self.reset();
+		self.with_search_state(|graph, state| {
+			graph.run_search(state, &[source], Some(target), f64::INFINITY, |node| {
+				let distance = haversine_meters(node.latitude, node.longitude,
+					target_latitude, target_longitude);
+				match max_vehicle_speed {
+					u64::MAX => distance,
+					// Same formula as for the arc travel time
+					_ => distance * 3.6 / max_vehicle_speed as f64
+				}
+			});
+		});
+		
+		Ok(self.get_shortest_path(target))
+	}
+	
+	/// Select landmarks and compute the costs from and to them for
+	/// compute_shortest_path_alt.
+	///
+	/// The first landmark is the furthest node from node 0, every further
+	/// landmark is the node furthest from all selected landmarks. The
+	/// landmarks have to be computed again after the arcs or costs change.
+	pub fn precompute_landmarks(&mut self, count: usize) {
+		self.landmarks.clear();
+		if self.nodes.is_empty() {
+			return;
+		}
+		
+		let mut reversed = if self.directed { Some(self.reversed()) } else { None };
+		let mut is_landmark = vec![false; self.num_nodes()];
+		let mut min_distances = vec![f64::INFINITY; self.num_nodes()];
+		
+		self.reset();
+		self.run_dijkstra(0, None);
+		let mut landmark = self.get_furthest_node().1;
+		
+		for _ in 0..min(count, self.num_nodes()) {
+			let distances_from = self.compute_distances(landmark);
+			let distances_to = match reversed {
+				Some(ref mut reversed) => reversed.compute_distances(landmark),
+				None => distances_from.clone()
+			};
+			
+			is_landmark[landmark] = true;
+			for (min_distance, &distance) in min_distances.iter_mut().zip(distances_from.iter()) {
+				*min_distance = min_distance.min(distance);
+			}
+			self.landmarks.push(Landmark {
+				distances_from: distances_from,
+				distances_to: distances_to
+			});
+			
+			// Unreachable nodes are the furthest, thus other components get landmarks too
+			let mut furthest: Option<(f64, usize)> = None;
+			for node_id in 0..self.num_nodes() {
+				if !is_landmark[node_id]
+						&& furthest.map_or(true, |(distance, _)| min_distances[node_id] > distance) {
+					furthest = Some((min_distances[node_id], node_id));
+				}
+			}
+			match furthest {
+				Some((_, node_id)) => { landmark = node_id; },
+				None => { break; }
+			}
+		}
+	}
+	
+	/// Compute the shortest path between the source and the target node
+	/// using an A* search with the landmark (ALT) heuristic.
+	///
+	/// The triangle inequality with the costs from and to the landmarks of
+	/// precompute_landmarks gives a lower bound of the costs to the target.
+	/// Without landmarks this is Dijkstra's algorithm. The result is the same
+	/// as for compute_shortest_path.
+	pub fn compute_shortest_path_alt(&mut self, source: usize, target: usize)
+			-> Result<Option<(f64, Vec<usize>)>> {
+		try!(self.check_node_id(source));
+		try!(self.check_node_id(target));
+		
+		self.reset();
+		self.with_search_state(|graph, state| {
+			graph.run_search(state, &[source], Some(target), f64::INFINITY, |node| {
+				let mut bound: f64 = 0.0;
+				for landmark in graph.landmarks.iter() {
+					// d(v, t) >= d(L, t) - d(L, v) and d(v, t) >= d(v, L) - d(t, L)
+					let forward = landmark.distances_from[target] - landmark.distances_from[node.id];
+					let backward = landmark.distances_to[node.id] - landmark.distances_to[target];
+					if forward.is_finite() {
+						bound = bound.max(forward);
+					}
+					if backward.is_finite() {
+						bound = bound.max(backward);
+					}
+				}
+				bound
+			});
+		});
+		
+		Ok(self.get_shortest_path(target))
+	}
+	
+	/// Compute the costs from the source node to every node, unreachable
+	/// nodes have infinite costs.
+	fn compute_distances(&mut self, source: usize) -> Vec<f64> {
+		self.reset();
+		self.run_dijkstra(source, None);
+		
+		(0..self.num_nodes())
+			.map(|node_id| self.search_state.distance(node_id).unwrap_or(f64::INFINITY))
+			.collect()
+	}
+	
+	/// Returns the costs and the nodes of the path to the end node.
+	fn get_shortest_path(&self, end_node: usize) -> Option<(f64, Vec<usize>)> {
+		self.search_state.shortest_path(end_node)
+	}
+	
+	/// Compute the shortest path between the source and the target node
+	/// with a bidirectional Dijkstra search.
+	///
+	/// A forward search from the source and a backward search on the
+	/// reversed arcs from the target run alternately and stop once their
+	/// frontiers can not improve the best meeting node anymore. The result
+	/// is the same as for compute_shortest_path. The arcs entering the nodes
+	/// of a directed graph are collected by the first search and kept until
+	/// the arcs change, an undirected graph contains the reversed arcs already.
+	pub fn compute_shortest_path_bidirectional(&mut self, source: usize, target: usize)
+			-> Result<Option<(f64, Vec<usize>)>> {
+		try!(self.check_node_id(source));
+		try!(self.check_node_id(target));
+		
+		if self.directed && self.incoming_arcs.is_none() {
+			self.incoming_arcs = Some(self.collect_incoming_arcs());
+		}
+		
+		let mut forward = mem::take(&mut self.search_state);
+		let mut backward = mem::take(&mut self.backward_search_state);
+		forward.reset(self.num_nodes());
+		backward.reset(self.num_nodes());
+		
+		let path = self.run_bidirectional_search(&mut forward, &mut backward, source, target);
+		self.search_state = forward;
+		self.backward_search_state = backward;
+		
+		Ok(path)
+	}
+	
+	/// Collect the arcs entering each node as (tail node id, arc index) pairs.
+	fn collect_incoming_arcs(&self) -> Vec<Vec<(usize, usize)>> {
+		let mut incoming_arcs = vec![Vec::new(); self.num_nodes()];
+		
+		for (tail_node, arcs) in self.adjacency_lists.iter().enumerate() {
+			for (arc_index, arc) in arcs.iter().enumerate() {
+				incoming_arcs[arc.head_node_id].push((tail_node, arc_index));
+			}
+		}
+		
+		incoming_arcs
+	}
+	
+	/// Run the forward search from the source and the backward search from
+	/// the target node with the given search states, which have to be reset
+	/// before.
+	///
+	/// The traceback of the backward search points towards the target.
+	fn run_bidirectional_search(&self, forward: &mut SearchState, backward: &mut SearchState,
+			source: usize, target: usize) -> Option<(f64, Vec<usize>)> {
+		// Index 0 is the forward search, index 1 the backward search
+		let states = [forward, backward];
+		let mut active_nodes = [MinQueue::new(), MinQueue::new()];
+		let mut next_nodes: Vec<(usize, usize, f64)> = Vec::new();
+		let mut best: Option<(f64, usize)> = None;
+		
+		states[0].distances[source] = Some(0.0);
+		states[1].distances[target] = Some(0.0);
+		active_nodes[0].push(CostKey(0.0), source);
+		active_nodes[1].push(CostKey(0.0), target);
+		if source == target {
+			best = Some((0.0, source));
+		}
+		
+		loop {
+			let mut min_keys = [f64::INFINITY; 2];
+			for direction in 0..2 {
+				if let Some(CostKey(key)) = active_nodes[direction].min_key() {
+					min_keys[direction] = key;
+				}
+			}
+			
+			// No path via an unsettled node can be shorter than the best path
+			let bound = match best { Some((costs, _)) => costs, None => f64::INFINITY };
+			if min_keys[0] == f64::INFINITY || min_keys[1] == f64::INFINITY
+					|| min_keys[0] + min_keys[1] >= bound {
+				break;
+			}
+			
+			let direction = if min_keys[0] <= min_keys[1] { 0 } else { 1 };
+			let (CostKey(key), node_id) = active_nodes[direction].pop().unwrap();
+			let distance = states[direction].distances[node_id].unwrap();
+			
+			// Node was already settled or the entry is outdated
+			if states[direction].settled[node_id] || key > distance {
+				continue;
+			}
+			states[direction].settled[node_id] = true;
+			
+			// Entries of (next node id, arc index, costs), the arc index of the
+			// backward search refers to the incoming arcs of a directed graph
+			next_nodes.clear();
+			match (direction, self.incoming_arcs.as_ref()) {
+				(1, Some(incoming_arcs)) => {
+					next_nodes.extend(incoming_arcs[node_id].iter().enumerate()
+						.map(|(index, &(tail_node, arc_index))| {
+							(tail_node, index, self.adjacency_lists[tail_node][arc_index].costs)
+						}));
+				},
+				_ => {
+					next_nodes.extend(self.adjacency_lists[node_id].iter().enumerate()
+						.map(|(arc_index, arc)| (arc.head_node_id, arc_index, arc.costs)));
+				}
+			}
+			
+			for &(next_node, arc_index, costs) in next_nodes.iter() {
+				let next_distance = distance + costs;
+				if states[direction].settled[next_node] || !next_distance.is_finite() {
+					continue;
+				}
+				match states[direction].distances[next_node] {
+					Some(known) if known <= next_distance => { continue; },
+					_ => { }
+				}
+				
+				states[direction].distances[next_node] = Some(next_distance);
+				states[direction].traceback_arcs[next_node] = Some((node_id, arc_index));
+				active_nodes[direction].push(CostKey(next_distance), next_node);
+				
+				if let Some(other_distance) = states[1 - direction].distances[next_node] {
+					let costs = next_distance + other_distance;
+					if costs.is_finite() && best.map_or(true, |(best_costs, _)| costs < best_costs) {
+						best = Some((costs, next_node));
+					}
+				}
+			}
+		}
+		
+		best.map(|(costs, meeting_node)| {
+			let mut path = states[0].traceback_path(meeting_node);
+			let mut backward_path = states[1].traceback_path(meeting_node);
+			backward_path.reverse();
+			path.extend_from_slice(&backward_path[1..]);
+			
+			(costs, path)
+		})
+	}
+	
+	/// Compute up to k loopless shortest paths between the source and the
+	/// target node with Yen's algorithm.
+	///
+	/// The result contains the path costs and nodes in order of increasing
+	/// costs and has less than k entries if there are no more paths. The arcs
+	/// and nodes excluded from a spur path are blocked by setting their costs
+	/// to infinity during the search, afterwards the costs are restored.
+	pub fn k_shortest_paths(&mut self, source: usize, target: usize, k: usize)
+			-> Result<Vec<(f64, Vec<usize>)>> {
+		try!(self.check_node_id(source));
+		try!(self.check_node_id(target));
+		
+		// Paths are stored with the costs to each of their nodes
+		let mut paths: Vec<(Vec<usize>, Vec<f64>)> = Vec::new();
+		let mut candidates: Vec<(Vec<usize>, Vec<f64>)> = Vec::new();
+		
+		if k == 0 {
+			return Ok(Vec::new());
+		}
+		
+		match self.compute_spur_path(source, target, &[]) {
+			Some(path) => { paths.push(path); },
+			None => { return Ok(Vec::new()); }
+		}
+		
+		while paths.len() < k {
+			let (last_nodes, last_costs) = paths[paths.len() - 1].clone();
+			
+			for spur_index in 0..last_nodes.len() - 1 {
+				let spur_node = last_nodes[spur_index];
+				let root_nodes = &last_nodes[..spur_index + 1];
+				
+				// Block the next arc of all paths with the same root and all
+				// arcs leaving the root nodes before the spur node
+				let mut blocked_arcs = Vec::new();
+				for &(ref nodes, _) in paths.iter() {
+					if nodes.len() > spur_index + 1 && &nodes[..spur_index + 1] == root_nodes {
+						blocked_arcs.push((spur_node, Some(nodes[spur_index + 1])));
+					}
+				}
+				for &node_id in &root_nodes[..spur_index] {
+					blocked_arcs.push((node_id, None));
+				}
+				
+				if let Some((spur_nodes, spur_costs)) = self.compute_spur_path(spur_node,
+						target, &blocked_arcs) {
+					let mut nodes = root_nodes.to_vec();
+					let mut costs = last_costs[..spur_index + 1].to_vec();
+					nodes.extend_from_slice(&spur_nodes[1..]);
+					costs.extend(spur_costs[1..].iter().map(|cost| cost + last_costs[spur_index]));
+					
+					if !candidates.iter().chain(paths.iter()).any(|path| path.0 == nodes) {
+						candidates.push((nodes, costs));
+					}
+				}
+			}
+			
+			if candidates.is_empty() {
+				break;
+			}
+			
+			// Take the cheapest candidate, ties are broken by the node ids
+			let mut best_index = 0;
+			for (index, candidate) in candidates.iter().enumerate() {
+				let best = &candidates[best_index];
+				let (costs, best_costs) = (candidate.1[candidate.1.len() - 1], best.1[best.1.len() - 1]);
+				if costs < best_costs || (costs == best_costs && candidate.0 < best.0) {
+					best_index = index;
+				}
+			}
+			paths.push(candidates.swap_remove(best_index));
+		}
+		
+		Ok(paths.into_iter().map(|(nodes, costs)| (costs[costs.len() - 1], nodes)).collect())
+	}
+	
+	/// Compute the shortest path between two nodes while the given arcs are
+	/// blocked, which are (tail node, head node) tuples. A head node of None
+	/// blocks all arcs of the tail node. The result contains the nodes of the
+	/// path and the costs to each of them.
+	fn compute_spur_path(&mut self, source: usize, target: usize,
+			blocked_arcs: &[(usize, Option<usize>)]) -> Option<(Vec<usize>, Vec<f64>)> {
+		let mut original_costs = Vec::new();
+		for &(tail_node, head_node) in blocked_arcs {
+			for (arc_index, arc) in self.adjacency_lists[tail_node].iter_mut().enumerate() {
+				if head_node.map_or(true, |head_node| arc.head_node_id == head_node) {
+					original_costs.push((tail_node, arc_index, arc.costs));
+					arc.costs = f64::INFINITY;
+				}
+			}
+		}
+		
+		// Arcs with infinite costs exceed the max distance and are skipped
+		self.reset();
+		self.with_search_state(|graph, state| {
+			graph.run_search(state, &[source], Some(target), f64::MAX, |_| 0.0);
+		});
+		let path = self.get_shortest_path(target).map(|(_, nodes)| {
+			let costs = nodes.iter().map(|&node_id| self.search_state.distance(node_id).unwrap())
+				.collect();
+			(nodes, costs)
+		});
+		
+		// Restore in reverse order, an arc might have been blocked twice
+		for &(tail_node, arc_index, costs) in original_costs.iter().rev() {
+			self.adjacency_lists[tail_node][arc_index].costs = costs;
+		}
+		
+		return path;
+	}
+	
+	/// Compute the shortest paths from the source node with the Bellman-Ford
+	/// algorithm.
+	///
+	/// The signed costs of the arcs are given by the cost function and may be
+	/// negative, the arc costs used by Dijkstra's algorithm are not modified.
+	/// The result contains the costs to each node or None if it is not
+	/// reachable. An error is returned if a negative cycle is reachable from
+	/// the source node.
+	pub fn compute_shortest_path_bellman_ford<F: Fn(&Arc) -> i64>(&self, source: usize,
+			costs: F) -> Result<Vec<Option<i64>>> {
+		try!(self.check_node_id(source));
+		
+		let mut distances = vec![None; self.num_nodes()];
+		distances[source] = Some(0);
+		
+		// After n - 1 rounds all shortest paths are found, an update in
+		// round n is only possible with a negative cycle
+		for round in 0..self.num_nodes() {
+			let mut updated = false;
+			
+			for arcs in self.adjacency_lists.iter() {
+				for arc in arcs.iter() {
+					let distance: i64 = match distances[arc.tail_node_id] {
+						Some(distance) => distance,
+						None => { continue; }
+					};
+					
+					// Sums which overflow are treated as unreachable
+					let next_distance = match distance.checked_add(costs(arc)) {
+						Some(next_distance) => next_distance,
+						None => { continue; }
+					};
+					
+					if distances[arc.head_node_id].map_or(true, |known| next_distance < known) {
+						if round + 1 == self.num_nodes() {
+							return Err(Error::format(format!(
+								"Negative cycle reachable from node {}!", source)));
+						}
+						
+						distances[arc.head_node_id] = Some(next_distance);
+						updated = true;
+					}
+				}
+			}
+			
+			if !updated {
+				break;
+			}
+		}
+		
+		Ok(distances)
+	}
+	
+	/// Collect the nodes on the traceback from the start node to the end node.
+	fn traceback_path(&self, end_node: usize) -> Vec<usize> {
+		self.search_state.traceback_path(end_node)
+	}
+	
+	/// Run Dijkstra's algorithm from the start node.
+	///
+	/// The search stops early once the target node (if any) is settled.
+	fn run_dijkstra(&mut self, start_node: usize, target_node: Option<usize>) {
+		self.with_search_state(|graph, state| {
+			graph.run_search(state, &[start_node], target_node, f64::INFINITY, |_| 0.0);
+		});
+	}
+	
+	/// Run the search with the search state of this graph.
+	fn with_search_state<R, F: FnOnce(&Graph, &mut SearchState) -> R>(&mut self, search: F) -> R {
+		let mut state = mem::take(&mut self.search_state);
+		state.resize(self.num_nodes());
+		let result = search(self, &mut state);
+		self.search_state = state;
+		
+		result
+	}
+	
+	/// Run an A* search from the start nodes.
+	///
+	/// The heuristic receives a node and has to return a lower bound
+	/// for the costs to the target node.
+	/// A heuristic of zero results in Dijkstra's algorithm. Nodes with
+	/// a distance above the max distance are not visited. The results are
+	/// stored in the search state, which has to be reset before.
+	fn run_search<F: Fn(&Node) -> f64>(&self, state: &mut SearchState, start_nodes: &[usize],
+			target_node: Option<usize>, max_distance: f64, heuristic: F) {
+		run_search_on(self, state, start_nodes, target_node, max_distance,
+			|node_id| heuristic(&self.nodes[node_id]));
+	}
+	
+	/// Compute the costs from the closest of the source nodes to every node.
+	///
+	/// The traceback of each node leads to the source it is closest to.
+	pub fn compute_multi_source(&mut self, sources: &[usize]) -> Result<Vec<Option<f64>>> {
+		for &source in sources {
+			try!(self.check_node_id(source));
+		}
+		
+		self.reset();
+		self.with_search_state(|graph, state| {
+			graph.run_search(state, sources, None, f64::INFINITY, |_| 0.0);
+		});
+		
+		Ok(self.search_state.distances.clone())
+	}
+	
+	/// Compute the costs between all pairs of the given nodes.
+	///
+	/// The entry [i][j] contains the costs of the shortest path from
+	/// nodes[i] to nodes[j] or None if it is not reachable. One Dijkstra
+	/// search is run per node.
+	pub fn cost_matrix(&mut self, nodes: &[usize]) -> Vec<Vec<Option<f64>>> {
+		let mut matrix = Vec::with_capacity(nodes.len());
+		
+		for &source in nodes {
+			self.reset();
+			self.run_dijkstra(source, None);
+			matrix.push(nodes.iter().map(|&target| self.search_state.distance(target)).collect());
+		}
+		
+		return matrix;
+	}
+	
+	/// Compute the closeness centrality of the given node.
+	///
+	/// The closeness is the number of other nodes reachable from the node
+	/// divided by the sum of the shortest path costs to them. The result is
+	/// 0.0 if no other node is reachable.
+	pub fn closeness_centrality(&mut self, node_id: usize) -> Result<f64> {
+		try!(self.check_node_id(node_id));
+		
+		Ok(self.compute_closeness(node_id))
+	}
+	
+	/// Compute the closeness centrality of an existing node.
+	fn compute_closeness(&mut self, node_id: usize) -> f64 {
+		self.reset();
+		self.run_dijkstra(node_id, None);
+		
+		let distances: Vec<f64> = self.search_state.distances.iter().filter_map(|&distance| distance)
+			.collect();
+		let total_costs: f64 = distances.iter().sum();
+		if distances.len() <= 1 || total_costs == 0.0 {
+			return 0.0;
+		}
+		
+		(distances.len() - 1) as f64 / total_costs
+	}
+	
+	/// Returns the node of the largest connected component with the highest
+	/// closeness centrality or None for an empty graph.
+	///
+	/// One Dijkstra search is run per node of the component.
+	pub fn most_central_node(&mut self) -> Option<usize> {
+		let (_, lcc) = self.compute_lcc();
+		let mut most_central: Option<(f64, usize)> = None;
+		
+		for &node_id in lcc.iter() {
+			let closeness = self.compute_closeness(node_id);
+			if most_central.map_or(true, |(best, _)| closeness > best) {
+				most_central = Some((closeness, node_id));
+			}
+		}
+		
+		most_central.map(|(_, node_id)| node_id)
+	}
+	
+	/// Compute all nodes reachable from the source within the cost budget.
+	///
+	/// The budget and the costs are given in the unit of the arc costs, which
+	/// are fractional for travel times. The result contains the reachable
+	/// nodes with their costs, sorted by node id. An error is returned for a
+	/// negative or NaN budget.
+	pub fn compute_reachable_within(&mut self, source: usize, max_cost: f64)
+			-> Result<Vec<(usize, f64)>> {
+		try!(self.check_node_id(source));
+		if max_cost.is_nan() || max_cost < 0.0 {
+			return Err(Error::format(format!("Invalid cost budget {}!", max_cost)));
+		}
+		
+		self.reset();
+		self.with_search_state(|graph, state| {
+			graph.run_search(state, &[source], None, max_cost, |_| 0.0);
+		});
+		
+		Ok((0..self.num_nodes())
+			.filter(|&node_id| self.search_state.is_settled(node_id))
+			.map(|node_id| (node_id, self.search_state.distance(node_id).unwrap()))
+			.collect())
+	}
+	
+	/// Summarize the path given by its nodes.
+	///
+	/// Between two nodes the arc with the lowest costs is used, the travel
+	/// time is computed as for travel_to. An error is returned if two
+	/// consecutive nodes are not connected by an arc.
+	pub fn path_summary(&self, path: &[usize], max_speed: u64) -> Result<PathSummary> {
+		let mut summary = PathSummary {
+			distance: 0.0,
+			travel_time: 0.0,
+			num_arcs: 0,
+			num_turns: 0
+		};
+		
+		for (index, nodes) in path.windows(2).enumerate() {
+			try!(self.check_node_id(nodes[0]));
+			try!(self.check_node_id(nodes[1]));
+			
+			let arc = match self.cheapest_arc(nodes[0], nodes[1]) {
+				Some(arc) => arc,
+				None => {
+					return Err(Error::format(format!("No arc from node {} to node {}!",
+						nodes[0], nodes[1])));
+				}
+			};
+			
+			summary.distance += arc.distance as f64;
+			summary.travel_time += travel_time(arc.distance, min(max_speed, arc.max_speed));
+			summary.num_arcs += 1;
+			
+			if index > 0 && self.adjacency_lists[nodes[0]].len() > 2 {
+				summary.num_turns += 1;
+			}
+		}
+		
+		Ok(summary)
+	}
+	
+	/// Compute distance and travel time of the selected path.
+	pub fn travel_to(&self, end_node: usize, max_speed: u64) -> (f64, f64) {
+		let mut node = &self.nodes[end_node];
+		let mut time = 0.0;
+		let mut distance = 0.0;
+		
+		loop {
+			let arc_ref = self.search_state.traceback_arc(node.id);
+			match arc_ref {
+				None => {
+					break;
+				},
+				Some((tail_node, arc_index)) => {
+					let arc = &self.adjacency_lists[tail_node][arc_index];
+					
+					distance += arc.distance as f64;
+					
+					// s = v * t => t = s / v
+					// t = [s] = s / v = [m] / [m/s] = [m] / ([km/h] / 3.6)
+					time += arc.distance as f64 * 3.6 / min(max_speed, arc.max_speed) as f64;
+					
+					// Follow to previous node
+					node = &self.nodes[arc.tail_node_id];
+				}
+			}
+		}
+		
+		return (distance, time);
+	}
+	
+	/// Resets this graph to enable a different Dijkstra calculation.
+	pub fn reset(&mut self) {
+		let num_nodes = self.num_nodes();
+		self.search_state.reset(num_nodes);
+	}
+	
+	/// Find the node closest to the given coordinates.
+	///
+	/// The result is None for an empty graph.
+	pub fn find_nearest_node(&self, latitude: f64, longitude: f64) -> Option<usize> {
+		find_nearest_of(&self.nodes, 0..self.num_nodes(), latitude, longitude)
+			.map(|(_, node_id)| node_id)
+	}
+	
+	/// Build a spatial index over the node coordinates.
+	pub fn build_spatial_index(&self) -> SpatialIndex {
+		SpatialIndex::new(self.nodes.iter().map(|node| (node.latitude, node.longitude)).collect())
+	}
+	
+	/// Returns the furthes node from the selected start
+	pub fn get_furthest_node(&self) -> (f64, usize) {
+		let mut max_dist = (0.0, 0);
+		
+		for node in self.nodes.iter() {
+			match self.search_state.distance(node.id) {
+				None => { },
+				Some(dist) => {
+					if max_dist.0 < dist {
+						max_dist = (dist, node.id);
+					}
+				}
+			}
+		}
+		
+		return max_dist;
+	}
+	
+	/// Estimate the diameter of the component of the first node.
+	///
+	/// Runs Dijkstra's algorithm from the first node to find the furthest node
+	/// and again from that node, the result are the costs to the furthest node
+	/// of the second search. This is a lower bound of the exact diameter,
+	/// which is the maximum over the shortest paths between all node pairs.
+	pub fn estimate_diameter(&mut self) -> f64 {
+		if self.nodes.is_empty() {
+			return 0.0;
+		}
+		
+		self.reset();
+		self.run_dijkstra(0, None);
+		let (_, furthest_node) = self.get_furthest_node();
+		
+		self.reset();
+		self.run_dijkstra(furthest_node, None);
+		self.get_furthest_node().0
+	}
+	
+	pub fn generate_mapbb(&self, str: &mut String, end_node: usize) {
+		let mut node = &self.nodes[end_node];
+		let mut node_count = 0;
+		
+		loop {
+			if node_count % MAPBB_NODE_DIVIDER == 0 {
+				if node_count != 0 {
+					str.push_str(" ");
+				}
+				
+				str.push_str(format!("{:.4},{:.4}", node.latitude, node.longitude).as_str());
+			}
+			
+			node_count += 1;
+			
+			let arc_ref = self.search_state.traceback_arc(node.id);
+			match arc_ref {
+				None => {
+					break;
+				},
+				Some((tail_node, _)) => {
+					// Follow to previous node
+					node = &self.nodes[tail_node];
+				}
+			}
+		}
+
+		// Append last node
+		if node_count % MAPBB_NODE_DIVIDER != 0 {
+			str.push_str(format!("{:.4},{:.4}", node.latitude, node.longitude).as_str());
+		}
+	}
+	
+	/// Generate a GPX 1.1 track with one track point per node of the path.
+	///
+	/// An error is returned if the path contains an invalid node id.
+	pub fn path_to_gpx(&self, path: &[usize]) -> Result<String> {
+		for &node_id in path {
+			try!(self.check_node_id(node_id));
+		}
+		
+		let mut gpx = String::new();
+		
+		gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+		gpx.push_str("<gpx version=\"1.1\" creator=\"ex13\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+		gpx.push_str("  <trk>\n    <trkseg>\n");
+		
+		for &node_id in path {
+			let node = &self.nodes[node_id];
+			gpx.push_str(format!("      <trkpt lat=\"{:.7}\" lon=\"{:.7}\"></trkpt>\n",
+				node.latitude, node.longitude).as_str());
+		}
+		
+		gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+		Ok(gpx)
+	}
+	
+	/// Returns all arcs of a directed graph or one arc per edge of an
+	/// undirected graph.
+	fn edge_arcs(&self) -> Vec<&Arc> {
+		let mut edges = Vec::new();
+		
+		for arcs in self.adjacency_lists.iter() {
+			let mut self_loops = 0;
+			
+			for arc in arcs.iter() {
+				if !self.directed {
+					// Skip the reverse arcs of undirected edges
+					if arc.tail_node_id > arc.head_node_id {
+						continue;
+					}
+					if arc.tail_node_id == arc.head_node_id {
+						self_loops += 1;
+						if self_loops % 2 == 0 {
+							continue;
+						}
+					}
+				}
+				
+				edges.push(arc);
+			}
+		}
+		
+		return edges;
+	}
+	
+	/// Generate a GraphViz DOT description of this graph.
+	///
+	/// Every arc is labeled with its costs, an undirected graph contains each
+	/// edge once. Graphs with more than max_nodes nodes are rejected.
+	pub fn to_dot(&self, max_nodes: usize) -> Result<String> {
+		if self.num_nodes() > max_nodes {
+			return Err(Error::format(format!("Graph too large for DOT export! ({} nodes, max {})",
+				self.num_nodes(), max_nodes)));
+		}
+		
+		let (keyword, connector) = if self.directed { ("digraph", "->") } else { ("graph", "--") };
+		
+		let mut dot = String::new();
+		dot.push_str(format!("{} {{\n", keyword).as_str());
+		
+		for node in self.nodes.iter() {
+			dot.push_str(format!("  {};\n", node.id).as_str());
+		}
+		
+		for arc in self.edge_arcs() {
+			dot.push_str(format!("  {} {} {} [label=\"{}\"];\n", arc.tail_node_id, connector,
+				arc.head_node_id, arc.costs).as_str());
+		}
+		
+		dot.push_str("}\n");
+		Ok(dot)
+	}
+	
+	/// Generate a GeoJSON feature collection of this graph.
+	///
+	/// Every node is a point with its id, every arc (one per edge of an
+	/// undirected graph) is a line string with its costs and max speed.
+	/// GeoJSON coordinates are in longitude, latitude order.
+	pub fn to_geojson(&self) -> String {
+		let mut features = Vec::new();
+		
+		for node in self.nodes.iter() {
+			features.push(format!("{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"Point\",\
+				\"coordinates\":[{},{}]}},\"properties\":{{\"id\":{}}}}}",
+				node.longitude, node.latitude, node.id));
+		}
+		
+		for arc in self.edge_arcs() {
+			let (tail_node, head_node) = (&self.nodes[arc.tail_node_id], &self.nodes[arc.head_node_id]);
+			features.push(format!("{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"LineString\",\
+				\"coordinates\":[[{},{}],[{},{}]]}},\"properties\":{{\"tail\":{},\"head\":{},\
+				\"costs\":{},\"max_speed\":{}}}}}", tail_node.longitude, tail_node.latitude,
+				head_node.longitude, head_node.latitude, arc.tail_node_id, arc.head_node_id,
+				arc.costs, arc.max_speed));
+		}
+		
+		format!("{{\"type\":\"FeatureCollection\",\"features\":[{}]}}", features.join(","))
+	}
+	
+	/// Compute a minimum spanning tree with Kruskal's algorithm.
+	///
+	/// The arcs are weighted by their distance and treated as undirected
+	/// edges. The result contains the (tail, head, distance) edges of the
+	/// tree, or of a spanning forest if the graph is not connected.
+	pub fn minimum_spanning_tree(&self) -> Vec<(usize, usize, u64)> {
+		let mut edges = self.edge_arcs();
+		edges.sort_by_key(|arc| arc.distance);
+		
+		let mut sets = UnionFind::new(self.num_nodes());
+		let mut tree = Vec::new();
+		
+		for arc in edges {
+			if sets.union(arc.tail_node_id, arc.head_node_id) {
+				tree.push((arc.tail_node_id, arc.head_node_id, arc.distance));
+			}
+		}
+		
+		return tree;
+	}
+	
+	/// Compute a minimum spanning tree with Prim's algorithm.
+	///
+	/// The tree is grown from the start node along the arcs weighted by their
+	/// distance, thus it only spans the component of the start node and the
+	/// graph should be undirected. The result contains the (tail, head,
+	/// distance) edges in the order they were added to the tree.
+	pub fn minimum_spanning_tree_prim(&self, start_node: usize) -> Result<Vec<(usize, usize, u64)>> {
+		try!(self.check_node_id(start_node));
+		
+		// Min-heap with entries of (distance, (head node id, tail node id))
+		let mut active_arcs = MinQueue::new();
+		let mut in_tree = vec![false; self.num_nodes()];
+		let mut tree = Vec::new();
+		
+		in_tree[start_node] = true;
+		for arc in self.adjacency_lists[start_node].iter() {
+			active_arcs.push(arc.distance, (arc.head_node_id, start_node));
+		}
+		
+		while let Some((distance, (node_id, tail_node))) = active_arcs.pop() {
+			if in_tree[node_id] {
+				continue;
+			}
+			
+			in_tree[node_id] = true;
+			tree.push((tail_node, node_id, distance));
+			
+			for arc in self.adjacency_lists[node_id].iter() {
+				if !in_tree[arc.head_node_id] {
+					active_arcs.push(arc.distance, (arc.head_node_id, node_id));
+				}
+			}
+		}
+		
+		Ok(tree)
+	}
+	
+	/// Extract the subgraph induced by the given nodes.
+	///
+	/// The selected nodes are renumbered to 0..k in the given order, repeated
+	/// ids are ignored. Only arcs between selected nodes are kept. The result
+	/// contains the subgraph and the original id of each new node, an error
+	/// is returned for invalid node ids.
+	pub fn induced_subgraph(&self, nodes: &[usize]) -> Result<(Graph, Vec<usize>)> {
+		for &node_id in nodes {
+			try!(self.check_node_id(node_id));
+		}
+		
+		Ok(self.extract_subgraph(nodes))
+	}
+	
+	/// Extract the subgraph induced by the given existing nodes.
+	///
+	/// The nodes keep their ids in the source file, see original_id.
+	fn extract_subgraph(&self, nodes: &[usize]) -> (Graph, Vec<usize>) {
+		let mut graph = Graph::with_options(GraphBuildOptions { directed: self.directed,
+			distance_unit: self.distance_unit, ..Default::default() });
+		let mut new_ids = vec![usize::MAX; self.num_nodes()];
+		let mut original_ids = Vec::with_capacity(nodes.len());
+		
+		for &node_id in nodes {
+			if new_ids[node_id] != usize::MAX {
+				continue;
+			}
+			
+			let node = &self.nodes[node_id];
+			new_ids[node_id] = graph.add_node(node.latitude, node.longitude);
+			original_ids.push(node_id);
+		}
+		
+		graph.copy_arcs(original_ids.iter().flat_map(|&node_id| self.adjacency_lists[node_id].iter()),
+			&new_ids);
+		
+		graph.original_ids = Some(original_ids.iter().map(|&node_id| match self.original_ids {
+			Some(ref file_ids) => file_ids[node_id],
+			None => node_id as u64
+		}).collect());
+		
+		(graph, original_ids)
+	}
+	
+	/// Merge two graphs into one graph.
+	///
+	/// The nodes of b are appended to the nodes of a, except for the nodes
+	/// matched to a node of a by the (a node, b node) pairs, which are unified
+	/// with it. The arcs of both graphs are kept. If one of the graphs has
+	/// sparse ids the file ids of both graphs are kept, see original_id.
+	pub fn merge(a: Graph, b: Graph, node_matches: &[(usize, usize)]) -> Result<Graph> {
+		if a.directed != b.directed {
+			return Err(Error::format("Cannot merge a directed and an undirected graph!"));
+		}
+		
+		let mut graph = a;
+		let mut new_ids = vec![usize::MAX; b.num_nodes()];
+		
+		for &(a_node, b_node) in node_matches {
+			try!(graph.check_node_id(a_node));
+			try!(b.check_node_id(b_node));
+			new_ids[b_node] = a_node;
+		}
+		
+		let mut original_ids = if graph.original_ids.is_none() && b.original_ids.is_none() {
+			None
+		} else {
+			Some((0..graph.num_nodes()).map(|node_id| graph.original_id(node_id)
+				.unwrap_or(node_id as u64)).collect::<Vec<u64>>())
+		};
+		
+		for (node_id, node) in b.nodes.iter().enumerate() {
+			if new_ids[node_id] == usize::MAX {
+				new_ids[node_id] = graph.add_node(node.latitude, node.longitude);
+				if let Some(ref mut original_ids) = original_ids {
+					original_ids.push(b.original_id(node_id).unwrap_or(node_id as u64));
+				}
+			}
+		}
+		
+		graph.copy_arcs(b.adjacency_lists.iter().flat_map(|arcs| arcs.iter()), &new_ids);
+		
+		graph.original_ids = original_ids;
+		graph.landmarks.clear();
+		graph.search_state = SearchState::default();
+		graph.backward_search_state = SearchState::default();
+		graph.incoming_arcs = None;
+		
+		Ok(graph)
+	}
+	
+	/// Append the given arcs with their nodes renumbered by new_ids.
+	///
+	/// Arcs to nodes without a new id (usize::MAX) are skipped. The arcs are
+	/// pushed as they are instead of using add_arc, as the source graph of an
+	/// undirected graph already contains the reverse arc of each edge.
+	fn copy_arcs<'a, I: Iterator<Item = &'a Arc>>(&mut self, arcs: I, new_ids: &[usize]) {
+		for arc in arcs {
+			if new_ids[arc.head_node_id] == usize::MAX {
+				continue;
+			}
+			
+			let tail_node = new_ids[arc.tail_node_id];
+			self.adjacency_lists[tail_node].push(Arc {
+				head_node_id: new_ids[arc.head_node_id],
+				tail_node_id: tail_node,
+				distance: arc.distance,
+				max_speed: arc.max_speed,
+				costs: arc.costs
+			});
+		}
+	}
+	
+	/// Returns the ids of all nodes inside the given bounding box.
+	///
+	/// A min longitude above the max longitude denotes a box crossing the
+	/// antimeridian, which contains the longitudes from min to 180 and from
+	/// -180 to max. The bounds are inclusive.
+	pub fn nodes_in_bbox(&self, min_latitude: f64, min_longitude: f64, max_latitude: f64,
+			max_longitude: f64) -> Vec<usize> {
+		let wraps = min_longitude > max_longitude;
+		
+		self.nodes.iter().filter(|node| {
+			let inside_longitude = if wraps {
+				node.longitude >= min_longitude || node.longitude <= max_longitude
+			} else {
+				node.longitude >= min_longitude && node.longitude <= max_longitude
+			};
+			
+			inside_longitude && node.latitude >= min_latitude && node.latitude <= max_latitude
+		}).map(|node| node.id).collect()
+	}
+	
+	/// Extract the subgraph of all nodes inside the given bounding box.
+	///
+	/// The result is the same as for induced_subgraph with the nodes of
+	/// nodes_in_bbox.
+	pub fn subgraph_in_bbox(&self, min_latitude: f64, min_longitude: f64, max_latitude: f64,
+			max_longitude: f64) -> (Graph, Vec<usize>) {
+		self.extract_subgraph(&self.nodes_in_bbox(min_latitude, min_longitude,
+			max_latitude, max_longitude))
+	}
+	
+	/// Create a copy of this graph with the direction of all arcs reversed.
+	///
+	/// The arcs keep their distance, speed and costs. An undirected graph
+	/// already contains both directions and is copied unchanged.
+	pub fn reversed(&self) -> Graph {
+		let mut graph = Graph::with_options(GraphBuildOptions { directed: self.directed,
+			distance_unit: self.distance_unit, ..Default::default() });
+		
+		for node in self.nodes.iter() {
+			graph.add_node(node.latitude, node.longitude);
+		}
+		graph.original_ids = self.original_ids.clone();
+		
+		if !self.directed {
+			graph.adjacency_lists = self.adjacency_lists.clone();
+			return graph;
+		}
+		
+		for arcs in self.adjacency_lists.iter() {
+			for arc in arcs.iter() {
+				graph.adjacency_lists[arc.head_node_id].push(Arc {
+					head_node_id: arc.tail_node_id,
+					tail_node_id: arc.head_node_id,
+					distance: arc.distance,
+					max_speed: arc.max_speed,
+					costs: arc.costs
+				});
+			}
+		}
+		
+		return graph;
+	}
+	
+	/// Convert this graph into the compressed sparse row format.
+	pub fn to_csr(&self) -> CsrGraph {
+		let mut offsets = Vec::with_capacity(self.num_nodes() + 1);
+		let mut arcs = Vec::with_capacity(self.num_arcs());
+		
+		offsets.push(0);
+		for adjacency_list in self.adjacency_lists.iter() {
+			arcs.extend(adjacency_list.iter().cloned());
+			offsets.push(arcs.len());
+		}
+		
+		CsrGraph {
+			offsets: offsets,
+			arcs: arcs
+		}
+	}
+}
+
+impl Adjacency for Graph {
+	
+	fn node_count(&self) -> usize {
+		self.adjacency_lists.len()
+	}
+	
+	fn arcs_of(&self, node_id: usize) -> &[Arc] {
+		&self.adjacency_lists[node_id]
+	}
+}
+
+impl Display for Graph {
+	fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+		try!(write!(fmt, "["));
+		for (i, node) in self.nodes.iter().enumerate() {
+			let lst = &self.adjacency_lists[node.id];
+			
+			for (j, adj) in lst.iter().enumerate() {
+				try!(write!(fmt, "{}->{}({})", adj.tail_node_id, adj.head_node_id, adj.costs));
+
+				if i + 1 != self.nodes.len() || j + 1 != lst.len() {
+					try!(write!(fmt, ", "));
+				}
+			}
+		}
+		try!(write!(fmt, "]"));
+
+		return Ok(());
+	}
+}
+
+/// Uniform latitude / longitude grid for fast nearest node queries.
+///
+/// The grid has about one cell per node, a query searches the cells
+/// ring by ring around the query point until no closer node can follow.
+/// The grid does not wrap at the antimeridian, nodes on the other side of
+/// it are found by searching further rings.
+pub struct SpatialIndex {
+	coordinates: Vec<(f64, f64)>,
+	cells: Vec<Vec<usize>>,
+	rows: usize,
+	columns: usize,
+	min_latitude: f64,
+	min_longitude: f64,
+	max_longitude: f64,
+	cell_height: f64,
+	cell_width: f64,
+	max_abs_latitude: f64
+}
+
+impl SpatialIndex {
+	
+	fn new(coordinates: Vec<(f64, f64)>) -> SpatialIndex {
+		let size = ((coordinates.len() as f64).sqrt().ceil() as usize).max(1);
+		
+		let mut index = SpatialIndex {
+			coordinates: Vec::new(),
+			cells: Vec::new(),
+			rows: size,
+			columns: size,
+			min_latitude: coordinates.iter().map(|c| c.0).fold(f64::INFINITY, f64::min),
+			min_longitude: coordinates.iter().map(|c| c.1).fold(f64::INFINITY, f64::min),
+			max_longitude: coordinates.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max),
+			cell_height: 1.0,
+			cell_width: 1.0,
+			max_abs_latitude: coordinates.iter().map(|c| c.0.abs()).fold(0.0, f64::max)
+		};
+		
+		let max_latitude = coordinates.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max);
+		
+		if max_latitude > index.min_latitude {
+			index.cell_height = (max_latitude - index.min_latitude) / size as f64;
+		}
+		if index.max_longitude > index.min_longitude {
+			index.cell_width = (index.max_longitude - index.min_longitude) / size as f64;
+		}
+		
+		index.cells = vec![Vec::new(); size * size];
+		for (node_id, &(latitude, longitude)) in coordinates.iter().enumerate() {
+			let (row, column) = index.cell_of(latitude, longitude);
+			let row = min(row.max(0) as usize, size - 1);
+			let column = min(column.max(0) as usize, size - 1);
+			
+			index.cells[row * size + column].push(node_id);
+		}
+		
+		index.coordinates = coordinates;
+		return index;
+	}
+	
+	/// Returns the row and column of the cell containing the coordinates.
+	///
+	/// The cell might be outside of the grid.
+	fn cell_of(&self, latitude: f64, longitude: f64) -> (i64, i64) {
+		(((latitude - self.min_latitude) / self.cell_height).floor() as i64,
+			((longitude - self.min_longitude) / self.cell_width).floor() as i64)
+	}
+	
+	/// Returns the grid cells with the given Chebyshev distance to a cell.
+	fn ring_cells(&self, row: i64, column: i64, ring: i64) -> Vec<usize> {
+		let mut cells = Vec::new();
+		
+		let first_row = (row - ring).max(0);
+		let last_row = (row + ring).min(self.rows as i64 - 1);
+		let first_column = (column - ring).max(0);
+		let last_column = (column + ring).min(self.columns as i64 - 1);
+		
+		for r in first_row..last_row + 1 {
+			if (r - row).abs() == ring {
+				for c in first_column..last_column + 1 {
+					cells.push(r as usize * self.columns + c as usize);
+				}
+			} else {
+				for &c in [column - ring, column + ring].iter() {
+					if c >= first_column && c <= last_column {
+						cells.push(r as usize * self.columns + c as usize);
+					}
+				}
+			}
+		}
+		
+		return cells;
+	}
+	
+	/// Returns a lower bound in meters for the distance of the query
+	/// point to any node in the given ring of cells.
+	fn ring_lower_bound(&self, latitude: f64, longitude: f64, ring: i64) -> f64 {
+		if ring <= 1 {
+			return 0.0;
+		}
+		
+		// The query point can be anywhere in its own cell
+		let cells = (ring - 1) as f64;
+		
+		// Nodes in the upper or lower part of the ring
+		let latitude_bound = (cells * self.cell_height).to_radians() * EARTH_RADIUS_METERS;
+		
+		// Nodes in the left or right part of the ring, the meridians
+		// converge towards the poles. Across the antimeridian a node is
+		// closer than its longitude difference in the grid, at least by
+		// 360 degrees minus the largest difference to the query point.
+		let max_latitude = self.max_abs_latitude.max(latitude.abs()).min(90.0);
+		let max_delta_longitude = (longitude - self.min_longitude).abs()
+			.max((self.max_longitude - longitude).abs());
+		let delta_longitude = (cells * self.cell_width).min(360.0 - max_delta_longitude)
+			.min(180.0).max(0.0).to_radians();
+		let longitude_bound = 2.0 * EARTH_RADIUS_METERS * max_latitude.to_radians().cos()
+			* (delta_longitude / 2.0).sin();
+		
+		latitude_bound.min(longitude_bound)
+	}
+	
+	/// Find the node closest to the given coordinates.
+	pub fn nearest(&self, latitude: f64, longitude: f64) -> Option<usize> {
+		self.k_nearest(latitude, longitude, 1).first().cloned()
+	}
+	
+	/// Find the k nodes closest to the given coordinates.
+	///
+	/// The nodes are sorted by distance, ties are broken by the node id.
+	pub fn k_nearest(&self, latitude: f64, longitude: f64, k: usize) -> Vec<usize> {
+		if k == 0 || self.coordinates.is_empty() {
+			return Vec::new();
+		}
+		
+		let (row, column) = self.cell_of(latitude, longitude);
+		let (last_row, last_column) = (self.rows as i64 - 1, self.columns as i64 - 1);
+		
+		// Rings which touch the grid
+		let first_ring = (-row).max(row - last_row).max(-column).max(column - last_column).max(0);
+		let last_ring = row.max(last_row - row).max(column).max(last_column - column);
+		
+		let mut nearest: Vec<(f64, usize)> = Vec::new();
+		
+		for ring in first_ring..last_ring + 1 {
+			if nearest.len() == k && nearest[k - 1].0 < self.ring_lower_bound(latitude, longitude, ring) {
+				break;
+			}
+			
+			for cell in self.ring_cells(row, column, ring) {
+				for &node_id in self.cells[cell].iter() {
+					let (node_latitude, node_longitude) = self.coordinates[node_id];
+					nearest.push((haversine_meters(latitude, longitude, node_latitude, node_longitude),
+						node_id));
+				}
+			}
+			
+			nearest.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)));
+			nearest.truncate(k);
+		}
+		
+		nearest.iter().map(|&(_, node_id)| node_id).collect()
+	}
+}
+
+/// Graph in compressed sparse row format.
+///
+/// All arcs are stored in one contiguous list, the arcs leaving
+/// node i are located at arcs[offsets[i]..offsets[i + 1]].
+pub struct CsrGraph {
+	offsets: Vec<usize>,
+	arcs: Vec<Arc>
+}
+
+impl CsrGraph {
+	
+	/// Returns the number of nodes in this graph.
+	pub fn num_nodes(&self) -> usize {
+		self.offsets.len() - 1
+	}
+	
+	/// Return the number of arcs in this graph.
+	pub fn num_arcs(&self) -> usize {
+		self.arcs.len()
+	}
+	
+	/// Compute all reachable nodes from the given start node.
+	///
+	/// See Graph::compute_reachable_nodes.
+	pub fn compute_reachable_nodes(&self, node_id: usize) -> (usize, Box<Vec<u8>>) {
+		reachable_nodes(self, node_id)
+	}
+	
+	/// Mark all nodes in the largest connected component.
+	///
+	/// See Graph::compute_lcc.
+	pub fn compute_lcc(&self) -> (usize, Box<Vec<usize>>) {
+		largest_reachable_set(self)
+	}
+	
+	/// Compute the shortest paths for a given start node.
+	///
+	/// The result contains the distance and the previous node on the
+	/// shortest path for every node (None if not reachable). An error is
+	/// returned for an invalid start node.
+	pub fn compute_shortest_paths(&self, start_node: usize)
+			-> Result<(Vec<Option<f64>>, Vec<Option<usize>>)> {
+		try!(self.check_node_id(start_node));
+		
+		let mut state = SearchState::default();
+		state.reset(self.num_nodes());
+		
+		run_search_on(self, &mut state, &[start_node], None, f64::INFINITY, |_| 0.0);
+		
+		let traceback = state.traceback_arcs.iter()
+			.map(|arc| arc.map(|(tail_node_id, _)| tail_node_id))
+			.collect();
+		Ok((state.distances, traceback))
+	}
+}
+
+impl Adjacency for CsrGraph {
+	
+	fn node_count(&self) -> usize {
+		self.offsets.len() - 1
+	}
+	
+	fn arcs_of(&self, node_id: usize) -> &[Arc] {
+		&self.arcs[self.offsets[node_id]..self.offsets[node_id + 1]]
+	}
+}
+
+/// Disjoint sets over the elements 0..n.
+///
+/// The sets are stored as trees with union by rank and path compression.
+pub struct UnionFind {
+	parents: Vec<usize>,
+	ranks: Vec<u8>
+}
+
+impl UnionFind {
+	
+	/// Create n sets which contain a single element each.
+	pub fn new(size: usize) -> UnionFind {
+		UnionFind {
+			parents: (0..size).collect(),
+			ranks: vec![0; size]
+		}
+	}
+	
+	/// Returns the representative of the set of the element.
+	///
+	/// The representative stays the same until the set is merged.
+	pub fn find(&mut self, element: usize) -> usize {
+		let mut root = element;
+		while self.parents[root] != root {
+			root = self.parents[root];
+		}
+		
+		// Path compression
+		let mut element = element;
+		while self.parents[element] != root {
+			let parent = self.parents[element];
+			self.parents[element] = root;
+			element = parent;
+		}
+		
+		root
+	}
+	
+	/// Merge the sets of both elements, returns false if they were
+	/// in the same set already.
+	pub fn union(&mut self, a: usize, b: usize) -> bool {
+		let root_a = self.find(a);
+		let root_b = self.find(b);
+		if root_a == root_b {
+			return false;
+		}
+		
+		if self.ranks[root_a] < self.ranks[root_b] {
+			self.parents[root_a] = root_b;
+		} else if self.ranks[root_a] > self.ranks[root_b] {
+			self.parents[root_b] = root_a;
+		} else {
+			self.parents[root_b] = root_a;
+			self.ranks[root_a] += 1;
+		}
+		
+		true
+	}
+	
+	/// Returns true if both elements are in the same set.
+	pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+		self.find(a) == self.find(b)
+	}
+}
+
+/// Returns the total distance of the given spanning tree edges.
+pub fn spanning_tree_weight(edges: &[(usize, usize, u64)]) -> u64 {
+	edges.iter().map(|&(_, _, distance)| distance).sum()
+}
+
+#[test]
+fn test_read() {
+	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+
+	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
+		format!("{}", graph));
+}
+
+#[test]
+fn test_reachable_nodes() {
+	let graph = Graph::read_graph_from_file("graphs/test2.zip").unwrap();
+
+	assert_eq!(4, graph.compute_reachable_nodes(0).0);
+	assert_eq!(6, graph.compute_reachable_nodes(4).0);
+	assert_eq!(1, graph.compute_reachable_nodes(6).0);
+}
+
+#[test]
+fn test_shortest() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+
+	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
+		format!("{}", graph));
+
+	graph.compute_shortest_paths(1);
+
+	let expected = vec![None, Some(0.0), Some(20.0), Some(70.0), None];
+	assert_eq!(expected,
+		graph.search_state.distances.clone());
+}
+
+#[test]
+fn test_costs_distance() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+
+	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
+		format!("{}", graph));
+
+	graph.set_arc_costs_to_travel_time(100);
+	graph.set_arc_costs_to_distance();
+
+	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
+		format!("{}", graph));
+}
+
+#[test]
+fn test_costs_time() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+
+	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
+		format!("{}", graph));
+
+	graph.set_arc_costs_to_travel_time(100);
+
+	assert_eq!("[0->1(3.6), 0->2(8.4), 1->2(2.4), 2->3(6), 3->1(4.8), 4->3(2.4)]",
+		format!("{}", graph));
+}
+
+#[test]
+fn test_shortest_path() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+
+	assert_eq!(Some((100.0, vec![0, 1, 2, 3])), graph.compute_shortest_path(0, 3).unwrap());
+	assert_eq!(Some((0.0, vec![2])), graph.compute_shortest_path(2, 2).unwrap());
+	assert_eq!(None, graph.compute_shortest_path(1, 0).unwrap());
+
+	match graph.compute_shortest_path(0, 5) {
+		Err(Error::FormatError { .. }) => { },
+		_ => { assert!(false); }
+	}
+}
+
+#[test]
+fn test_shortest_paths_brute_force() {
+	let mut graph = Graph::read_graph_from_file("graphs/test2.zip").unwrap();
+	let node_count = graph.num_nodes();
+
+	// All-pairs shortest paths by Floyd-Warshall
+	let mut expected = vec![vec![None; node_count]; node_count];
+	for i in 0..node_count {
+		expected[i][i] = Some(0.0);
+		for arc in graph.adjacency_lists[i].iter() {
+			if expected[i][arc.head_node_id].map_or(true, |d| arc.costs < d) {
+				expected[i][arc.head_node_id] = Some(arc.costs);
+			}
+		}
+	}
+	for k in 0..node_count {
+		for i in 0..node_count {
+			for j in 0..node_count {
+				if let (Some(a), Some(b)) = (expected[i][k], expected[k][j]) {
+					if expected[i][j].map_or(true, |d| a + b < d) {
+						expected[i][j] = Some(a + b);
+					}
+				}
+			}
+		}
+	}
+
+	for source in 0..node_count {
+		graph.reset();
+		graph.compute_shortest_paths(source);
+
+		assert_eq!(expected[source],
+			graph.search_state.distances.clone());
+
+		// The traceback has to reproduce the path costs
+		for target in 0..node_count {
+			if let Some(distance) = expected[source][target] {
+				let path = graph.traceback_path(target);
+				assert_eq!(source, path[0]);
+				assert_eq!(distance, graph.travel_to(target, u64::max_value()).0);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+fn build_graph(nodes: &[(f64, f64)], arcs: &[(usize, usize, u64, u64)]) -> Graph {
+	build_graph_with_options(GraphBuildOptions::default(), nodes, arcs)
+}
+
+/// Same as build_graph, the reverse arc of each edge is added by the graph.
+#[cfg(test)]
+fn build_undirected_graph(nodes: &[(f64, f64)], arcs: &[(usize, usize, u64, u64)]) -> Graph {
+	build_graph_with_options(GraphBuildOptions { directed: false, ..Default::default() }, nodes, arcs)
+}
+
+#[cfg(test)]
+fn build_graph_with_options(options: GraphBuildOptions, nodes: &[(f64, f64)],
+		arcs: &[(usize, usize, u64, u64)]) -> Graph {
+	let mut graph = Graph::with_options(options);
+	
+	for &(latitude, longitude) in nodes {
+		graph.add_node(latitude, longitude);
+	}
+	
+	for &(tail_node, head_node, distance, max_speed) in arcs {
+		graph.add_arc(tail_node, head_node, distance, max_speed).unwrap();
+	}
+	
+	graph
+}
+
+#[test]
+fn test_haversine() {
+	assert_eq!(0.0, haversine_meters(48.0, 7.8, 48.0, 7.8));
+	
+	// One degree of latitude is about 111.2 km
+	let distance = haversine_meters(48.0, 7.8, 49.0, 7.8);
+	assert!((distance - 111195.0).abs() < 1.0);
+}
+
+#[test]
+fn test_shortest_path_astar() {
+	// 3x3 grid with arcs in both directions and some detours
+	let mut nodes = Vec::new();
+	for i in 0..9 {
+		nodes.push((48.0 + (i / 3) as f64 * 0.01, 7.8 + (i % 3) as f64 * 0.01));
+	}
+	
+	let mut arcs = Vec::new();
+	for &(tail, head, detour, max_speed) in [(0, 1, 0, 50), (1, 2, 300, 100), (3, 4, 0, 30),
+			(4, 5, 0, 130), (6, 7, 100, 50), (7, 8, 0, 50), (0, 3, 0, 100), (3, 6, 50, 50),
+			(1, 4, 400, 100), (4, 7, 0, 30), (2, 5, 0, 50), (5, 8, 200, 130)].iter() {
+		let (tail_node, head_node) = (nodes[tail], nodes[head]);
+		let distance = haversine_meters(tail_node.0, tail_node.1, head_node.0, head_node.1)
+			.ceil() as u64 + detour;
+		
+		arcs.push((tail, head, distance, max_speed));
+		arcs.push((head, tail, distance, max_speed));
+	}
+	
+	let mut graph = build_graph(&nodes, &arcs);
+	
+	for &max_speed in [u64::MAX, 100].iter() {
+		match max_speed {
+			u64::MAX => { graph.set_arc_costs_to_distance(); },
+			_ => { graph.set_arc_costs_to_travel_time(max_speed); }
+		}
+		
+		for source in 0..9 {
+			for target in 0..9 {
+				let dijkstra = graph.compute_shortest_path(source, target).unwrap().unwrap();
+				let astar = graph.compute_shortest_path_astar(source, target, max_speed)
+					.unwrap().unwrap();
+				
+				assert!((dijkstra.0 - astar.0).abs() < 1e-9);
+			}
+		}
+	}
+	
+	graph.set_arc_costs_to_travel_time(0);
+	assert!(graph.compute_shortest_path_astar(0, 8, 0).is_err());
+	assert!(graph.compute_shortest_path_astar(8, 8, 0).is_err());
+}
+
+#[test]
+fn test_num_arcs() {
+	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	assert_eq!(5, graph.num_nodes());
+	assert_eq!(6, graph.num_arcs());
+	
+	// Three edges inserted in both directions
+	let graph = build_graph(&[(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0)],
+		&[(0, 1, 10, 50), (1, 0, 10, 50), (1, 2, 10, 50), (2, 1, 10, 50), (2, 3, 10, 50),
+			(3, 2, 10, 50)]);
+	assert_eq!(4, graph.num_nodes());
+	assert_eq!(6, graph.num_arcs());
+}
+
+#[test]
+fn test_read_directed() {
+	let data = "2\n1\n0 48.0 7.8\n1 48.1 7.8\n0 1 100 50\n";
+	
+	for &directed in [true, false].iter() {
+		let mut graph = Graph::with_options(GraphBuildOptions { directed: directed, ..Default::default() });
+		graph.read_lines(Cursor::new(data), None, None).unwrap();
+		
+		assert_eq!(1, graph.adjacency_lists[0].len());
+		assert_eq!(1, graph.adjacency_lists[0][0].head_node_id);
+		
+		if directed {
+			assert!(graph.adjacency_lists[1].is_empty());
+		} else {
+			assert_eq!(1, graph.adjacency_lists[1].len());
+			assert_eq!(0, graph.adjacency_lists[1][0].head_node_id);
+		}
+	}
+	
+	let graph = Graph::read_graph_from_file_with_options("graphs/test.zip",
+		GraphBuildOptions { directed: false, ..Default::default() }).unwrap();
+	assert!(!graph.is_directed());
+	assert_eq!(12, graph.num_arcs());
+}
+
+#[test]
+fn test_builder() {
+	let mut graph = Graph::with_options(GraphBuildOptions { directed: false, ..Default::default() });
+	
+	assert_eq!(0, graph.add_node(48.0, 7.8));
+	assert_eq!(1, graph.add_node(48.1, 7.8));
+	assert_eq!(2, graph.add_node(48.1, 7.9));
+	
+	graph.add_arc(0, 1, 30, 50).unwrap();
+	graph.add_arc(1, 2, 20, 50).unwrap();
+	assert!(graph.add_arc(1, 3, 20, 50).is_err());
+	
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(4, graph.num_arcs());
+	assert_eq!("[0->1(30), 1->0(30), 1->2(20), 2->1(20)]", format!("{}", graph));
+	
+	assert_eq!(Some((50.0, vec![2, 1, 0])), graph.compute_shortest_path(2, 0).unwrap());
+}
+
+#[test]
+fn test_lcc() {
+	// Components {0, 5} and {1, 2, 4, 6}, node 3 is isolated
+	let graph = build_undirected_graph(&[(0.0, 0.0); 7],
+		&[(0, 5, 10, 50), (1, 2, 10, 50), (2, 4, 10, 50), (6, 4, 10, 50)]);
+	
+	let (size, nodes) = graph.compute_lcc();
+	assert_eq!(4, size);
+	assert_eq!(vec![1, 2, 4, 6], *nodes);
+}
+
+#[test]
+fn test_components() {
+	// Components {0, 2, 4, 6, 7}, {1, 5} and {3}
+	let graph = build_undirected_graph(&[(0.0, 0.0); 8],
+		&[(0, 2, 10, 50), (2, 4, 10, 50), (4, 6, 10, 50), (7, 0, 10, 50), (5, 1, 10, 50)]);
+	
+	assert_eq!(vec![0, 1, 0, 2, 0, 1, 0, 0], graph.compute_components());
+	assert_eq!(3, graph.num_components());
+	assert_eq!(0, Graph::new().num_components());
+}
+
+#[test]
+fn test_read_from_reader() {
+	let mut data = String::new();
+	let mut archive = ZipArchive::new(File::open("graphs/test.zip").unwrap()).unwrap();
+	archive.by_index(0).unwrap().read_to_string(&mut data).unwrap();
+	
+	let graph = Graph::read_graph_from_reader(Cursor::new(data.as_bytes())).unwrap();
+	let zip_graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	
+	assert_eq!(zip_graph.num_nodes(), graph.num_nodes());
+	assert_eq!(zip_graph.num_arcs(), graph.num_arcs());
+	assert_eq!(format!("{}", zip_graph), format!("{}", graph));
+	
+	let path = env::temp_dir().join("ex13_test_read_from_reader.graph");
+	File::create(&path).unwrap().write_all(data.as_bytes()).unwrap();
+	let text_graph = Graph::read_graph_from_text_file(path.to_str().unwrap()).unwrap();
+	assert_eq!(format!("{}", zip_graph), format!("{}", text_graph));
+	
+	assert!(Graph::read_graph_from_text_file("graphs/missing.graph").is_err());
+}
+
+#[test]
+fn test_read_gzip() {
+	use self::flate2::Compression;
+	use self::flate2::write::GzEncoder;
+	
+	let data = "3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.1 7.9\n0 1 30 50\n2 1 20 50\n";
+	
+	let path = env::temp_dir().join("ex13_test_read_gzip.graph.gz");
+	let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+	encoder.write_all(data.as_bytes()).unwrap();
+	encoder.finish().unwrap();
+	
+	let graph = Graph::read_graph_from_file(path.to_str().unwrap()).unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!("[0->1(30), 2->1(20)]", format!("{}", graph));
+	
+	// Plain text is no valid gzip stream
+	let path = env::temp_dir().join("ex13_test_read_gzip_invalid.graph.gz");
+	File::create(&path).unwrap().write_all(data.as_bytes()).unwrap();
+	match Graph::read_graph_from_file(path.to_str().unwrap()) {
+		Err(Error::IoError(_)) => { },
+		_ => { assert!(false); }
+	}
+}
+
+#[test]
+fn test_csr() {
+	let mut graph = Graph::read_graph_from_file("graphs/test2.zip").unwrap();
+	let csr = graph.to_csr();
+	
+	assert_eq!(graph.num_nodes(), csr.num_nodes());
+	assert_eq!(graph.num_arcs(), csr.num_arcs());
+	
+	for node in 0..graph.num_nodes() {
+		assert_eq!(graph.compute_reachable_nodes(node), csr.compute_reachable_nodes(node));
+		
+		graph.reset();
+		graph.compute_shortest_paths(node);
+		
+		let (distances, traceback) = csr.compute_shortest_paths(node).unwrap();
+		assert_eq!(graph.search_state.distances.clone(),
+			distances);
+		assert_eq!(graph.search_state.traceback_arcs.iter().map(|arc| arc.map(|arc| arc.0))
+			.collect::<Vec<Option<usize>>>(), traceback);
+	}
+	assert!(csr.compute_shortest_paths(graph.num_nodes()).is_err());
+	assert_eq!(graph.compute_lcc(), csr.compute_lcc());
+	
+	let graph = Graph::read_graph_from_file_with_options("graphs/test2.zip",
+		GraphBuildOptions { directed: false, ..Default::default() }).unwrap();
+	assert_eq!(graph.compute_lcc(), graph.to_csr().compute_lcc());
+}
+
+#[test]
+fn test_traceback_parallel_arcs() {
+	// The cheaper of the parallel arcs 1->2 is not the first one
+	let mut graph = build_graph(&[(48.0, 7.8), (48.1, 7.8), (48.1, 7.9)],
+		&[(0, 1, 30, 50), (1, 2, 90, 50), (1, 2, 20, 10)]);
+	
+	graph.compute_shortest_paths(0);
+	
+	assert_eq!(Some((1, 1)), graph.search_state.traceback_arc(2));
+	assert_eq!(vec![0, 1, 2], graph.traceback_path(2));
+	
+	let (distance, time) = graph.travel_to(2, 100);
+	assert_eq!(50.0, distance);
+	assert!((time - (30.0 * 3.6 / 50.0 + 20.0 * 3.6 / 10.0)).abs() < 1e-9);
+}
+
+#[test]
+fn test_find_nearest_node() {
+	let graph = build_graph(&[(48.0, 7.8), (48.5, 7.9), (47.5, 8.5)], &[]);
+	
+	assert_eq!(Some(0), graph.find_nearest_node(48.1, 7.7));
+	assert_eq!(Some(1), graph.find_nearest_node(48.4, 8.0));
+	assert_eq!(Some(2), graph.find_nearest_node(47.0, 9.0));
+	assert_eq!(None, Graph::new().find_nearest_node(48.0, 7.8));
+}
+
+#[test]
+fn test_spatial_index() {
+	// Pseudo random nodes around Freiburg
+	let mut seed: u64 = 42;
+	let mut random = || {
+		seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+		(seed >> 11) as f64 / (1u64 << 53) as f64
+	};
+	
+	let mut nodes = Vec::new();
+	for _ in 0..500 {
+		nodes.push((47.9 + random() * 0.2, 7.7 + random() * 0.3));
+	}
+	let graph = build_graph(&nodes, &[]);
+	let index = graph.build_spatial_index();
+	
+	for i in 0..200 {
+		// Some queries are far outside of the grid
+		let spread = if i % 10 == 0 { 5.0 } else { 0.3 };
+		let latitude = 48.0 + (random() - 0.5) * spread;
+		let longitude = 7.85 + (random() - 0.5) * spread;
+		
+		assert_eq!(graph.find_nearest_node(latitude, longitude), index.nearest(latitude, longitude));
+		
+		let mut expected = (0..nodes.len()).map(|node_id| {
+			(haversine_meters(latitude, longitude, nodes[node_id].0, nodes[node_id].1), node_id)
+		}).collect::<Vec<(f64, usize)>>();
+		expected.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then(a.1.cmp(&b.1)));
+		
+		assert_eq!(expected.iter().take(5).map(|e| e.1).collect::<Vec<usize>>(),
+			index.k_nearest(latitude, longitude, 5));
+	}
+	
+	assert_eq!(500, index.k_nearest(48.0, 7.8, 1000).len());
+	assert_eq!(None, Graph::new().build_spatial_index().nearest(48.0, 7.8));
+	assert_eq!(Some(0), build_graph(&[(48.0, 7.8)], &[]).build_spatial_index().nearest(0.0, 0.0));
+}
+
+#[test]
+fn test_spatial_index_antimeridian() {
+	let mut nodes = Vec::new();
+	for i in 0..9 {
+		nodes.push(((i % 3) as f64 - 1.0, -120.0 + (i / 3) as f64 * 120.0));
+	}
+	nodes.push((0.0, 179.0));
+	nodes.push((0.0, -179.9));
+	let graph = build_graph(&nodes, &[]);
+	let index = graph.build_spatial_index();
+	
+	// The node at -179.9 is 0.15 degrees away, the node at 179.0 0.95 degrees
+	assert_eq!(Some(10), index.nearest(0.0, 179.95));
+	assert_eq!(vec![10, 9], index.k_nearest(0.0, 179.95, 2));
+	assert_eq!(Some(9), index.nearest(0.0, 178.9));
+	
+	for i in 0..20 {
+		let longitude = if i % 2 == 0 { 179.0 + i as f64 * 0.05 } else { -179.0 - i as f64 * 0.05 };
+		let latitude = (i as f64 - 10.0) * 0.1;
+		assert_eq!(graph.find_nearest_node(latitude, longitude), index.nearest(latitude, longitude));
+	}
+}
+
+#[test]
+fn test_costs_with() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	
+	graph.set_arc_costs_with(|arc| (arc.distance() * 2) as f64).unwrap();
+	assert_eq!("[0->1(60), 0->2(140), 1->2(40), 2->3(100), 3->1(80), 4->3(40)]",
+		format!("{}", graph));
+	assert_eq!(Some((200.0, vec![0, 1, 2, 3])), graph.compute_shortest_path(0, 3).unwrap());
+	
+	// Penalize the detour via node 1
+	graph.set_arc_costs_with(|arc| match (arc.tail_node_id(), arc.head_node_id()) {
+		(0, 1) => 100.0,
+		_ => arc.distance() as f64
+	}).unwrap();
+	assert_eq!(Some((120.0, vec![0, 2, 3])), graph.compute_shortest_path(0, 3).unwrap());
+	
+	// Invalid costs are rejected and the previous costs are kept
+	assert!(graph.set_arc_costs_with(|_| -1.0).is_err());
+	assert!(graph.set_arc_costs_with(|_| f64::NAN).is_err());
+	assert_eq!(Some((120.0, vec![0, 2, 3])), graph.compute_shortest_path(0, 3).unwrap());
+	
+	// A max speed of zero blocks the arc instead of resulting in NaN costs
+	let mut graph = build_graph(&[(0.0, 0.0); 2], &[(0, 1, 0, 0)]);
+	graph.set_arc_costs_to_travel_time(130);
+	assert_eq!(f64::INFINITY, graph.adjacency_lists[0][0].costs);
+	assert_eq!(None, graph.compute_shortest_path(0, 1).unwrap());
+}
+
+#[test]
+fn test_reachable_within() {
+	// Line graph 0 - 1 - 2 - 3 - 4 with growing arc costs
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 5],
+		&[(0, 1, 10, 50), (1, 2, 20, 50), (2, 3, 30, 50), (3, 4, 40, 50)]);
+	
+	assert_eq!(vec![(0, 0.0), (1, 10.0), (2, 30.0)], graph.compute_reachable_within(0, 59.0).unwrap());
+	assert_eq!(vec![(0, 0.0), (1, 10.0), (2, 30.0), (3, 60.0)],
+		graph.compute_reachable_within(0, 60.0).unwrap());
+	assert_eq!(vec![(1, 20.0), (2, 0.0)], graph.compute_reachable_within(2, 29.0).unwrap());
+	assert_eq!(vec![(4, 0.0)], graph.compute_reachable_within(4, 39.0).unwrap());
+	assert_eq!(5, graph.compute_reachable_within(0, f64::INFINITY).unwrap().len());
+	
+	assert!(graph.compute_reachable_within(5, 10.0).is_err());
+	assert!(graph.compute_reachable_within(0, -1.0).is_err());
+	assert!(graph.compute_reachable_within(0, f64::NAN).is_err());
+}
+
+#[test]
+fn test_multi_source() {
+	// Path graph 0 - 1 - 2 - 3 - 4 - 5 with unit costs
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 6],
+		&[(0, 1, 1, 50), (1, 2, 1, 50), (2, 3, 1, 50), (3, 4, 1, 50), (4, 5, 1, 50)]);
+	
+	assert_eq!(vec![Some(0.0), Some(1.0), Some(2.0), Some(2.0), Some(1.0), Some(0.0)],
+		graph.compute_multi_source(&[0, 5]).unwrap());
+	
+	assert_eq!(vec![0, 1, 2], graph.traceback_path(2));
+	assert_eq!(vec![5, 4, 3], graph.traceback_path(3));
+	
+	assert_eq!(vec![None; 6], graph.compute_multi_source(&[]).unwrap());
+	assert!(graph.compute_multi_source(&[0, 6]).is_err());
+}
+
+#[test]
+fn test_validate_distances() {
+	// 0.01 degree of latitude is about 1112 m
+	let mut graph = build_graph(&[(48.0, 7.8), (48.01, 7.8), (48.02, 7.8)],
+		&[(0, 1, 1112, 50), (1, 2, 1112, 50), (2, 0, 100, 50)]);
+	
+	assert_eq!(vec![(2, 0)], graph.validate_distances(5.0));
+	assert_eq!(3, graph.validate_distances(0.0).len());
+	
+	graph.recompute_distances_from_coordinates();
+	
+	assert!(graph.validate_distances(1.0).is_empty());
+	assert_eq!(2224, graph.adjacency_lists[2][0].distance);
+}
+
+#[test]
+fn test_neighbors() {
+	let graph = build_graph(&[(48.0, 7.8), (48.1, 7.8), (48.1, 7.9), (48.0, 7.9)],
+		&[(0, 1, 30, 50), (0, 2, 20, 50), (0, 3, 10, 50), (1, 0, 30, 50)]);
+	
+	assert_eq!(vec![(1, 30.0), (2, 20.0), (3, 10.0)],
+		graph.neighbors(0).unwrap().collect::<Vec<(usize, f64)>>());
+	assert_eq!(3, graph.degree(0).unwrap());
+	assert_eq!(1, graph.degree(1).unwrap());
+	assert_eq!(0, graph.neighbors(3).unwrap().count());
+	
+	assert!(graph.neighbors(4).is_err());
+	assert!(graph.degree(4).is_err());
+}
+
+#[test]
+fn test_node_coordinates() {
+	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	
+	assert_eq!(Some((49.3418, 7.30089)), graph.node_coordinates(0));
+	assert_eq!(Some((49.2581, 7.32786)), graph.node_coordinates(4));
+	assert_eq!(None, graph.node_coordinates(5));
+}
+
+#[test]
+fn test_read_invalid_arc_node() {
+	let data = "3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.1 7.9\n0 1 30 50\n1 99 20 50\n";
+	
+	match Graph::read_graph_from_reader(Cursor::new(data)) {
+		Err(Error::FormatError { message }) => { assert!(message.contains("line 7")); },
+		_ => { assert!(false); }
+	}
+}
+
+#[test]
+fn test_read_whitespace() {
+	let data = "3\n2\n0\t48.0\t7.8\n1  48.1  7.8\n2 \t48.1 7.9\n0\t1\t30\t50\n1  2  20   50\n";
+	let graph = Graph::read_graph_from_reader(Cursor::new(data)).unwrap();
+	
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(Some((48.1, 7.9)), graph.node_coordinates(2));
+	assert_eq!(vec![(2, 20.0)], graph.neighbors(1).unwrap().collect::<Vec<(usize, f64)>>());
+	
+	// The number of fields is still checked
+	assert!(Graph::read_graph_from_reader(Cursor::new("1\n0\n0\t48.0\t7.8\t1\n")).is_err());
+}
+
+#[test]
+fn test_read_bom_crlf() {
+	let data = "\u{feff}# Number of nodes.\r\n2\r\n1\r\n0 48.0 7.8\r\n1 48.1 7.8\r\n0 1 30 50\r\n";
+	let graph = Graph::read_graph_from_reader(Cursor::new(data)).unwrap();
+	
+	assert_eq!(2, graph.num_nodes());
+	assert_eq!(1, graph.num_arcs());
+	
+	let graph = Graph::read_graph_from_reader(Cursor::new(&data[3..])).unwrap();
+	assert_eq!(2, graph.num_nodes());
+}
+
+#[test]
+fn test_read_lenient() {
+	let data = "3\n4\n0 48.0 7.8\n1 48.1 7.8\n2 48.1 7.9\n0 1 30 50\n1 x 20 50\n1 2 20\n2 0 10 50\n";
+	
+	assert!(Graph::read_graph_from_reader(Cursor::new(data)).is_err());
+	
+	let (graph, errors) = Graph::read_graph_from_reader_lenient(Cursor::new(data)).unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!("[0->1(30), 2->0(10)]", format!("{}", graph));
+	
+	assert_eq!(2, errors.len());
+	match errors[0] {
+		Error::ParseIntError(_) => { },
+		_ => { assert!(false); }
+	}
+	match errors[1] {
+		Error::FormatError { ref message } => { assert!(message.contains("line 8")); },
+		_ => { assert!(false); }
+	}
+	
+	let (graph, errors) = Graph::read_graph_lenient("graphs/test.zip").unwrap();
+	assert_eq!(6, graph.num_arcs());
+	assert!(errors.is_empty());
+}
+
+#[test]
+fn test_error_source() {
+	let err = Graph::read_graph_from_file("graphs/missing.zip").err().unwrap();
+	
+	let source = err.source().unwrap();
+	assert_eq!(::std::io::ErrorKind::NotFound,
+		source.downcast_ref::<IOError>().unwrap().kind());
+	
+	assert!(Error::from("format").source().is_none());
+}
+
+#[test]
+fn test_error_format() {
+	match Error::format("Invalid graph file!") {
+		Error::FormatError { message } => { assert_eq!("Invalid graph file!", message); },
+		_ => { assert!(false); }
+	}
+	
+	let err: Box<dyn StdError> = Box::new(Error::format("boxed"));
+	assert_eq!("Format error: Format error: boxed", format!("{}", Error::from(err)));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_bincode() {
+	let mut graph = Graph::read_graph_from_file("graphs/test2.zip").unwrap();
+	graph.compute_shortest_paths(0);
+	
+	let path = env::temp_dir().join("ex13_test_bincode.bin");
+	graph.save_bincode(path.to_str().unwrap()).unwrap();
+	
+	let mut loaded = Graph::load_bincode(path.to_str().unwrap()).unwrap();
+	assert_eq!(graph.num_nodes(), loaded.num_nodes());
+	assert_eq!(graph.num_arcs(), loaded.num_arcs());
+	assert_eq!(format!("{}", graph), format!("{}", loaded));
+	assert_eq!(graph.node_coordinates(6), loaded.node_coordinates(6));
+	
+	// The search state is not serialized
+	assert!((0..loaded.num_nodes()).all(|node_id| loaded.search_state.distance(node_id).is_none()
+		&& !loaded.search_state.is_settled(node_id)));
+	assert_eq!(graph.compute_shortest_path(0, 3).unwrap(), loaded.compute_shortest_path(0, 3).unwrap());
+	
+	assert!(Graph::load_bincode("graphs/test.zip").is_err());
+}
+
+#[test]
+fn test_path_to_gpx() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	let (_, path) = graph.compute_shortest_path(0, 3).unwrap().unwrap();
+	
+	let gpx = graph.path_to_gpx(&path).unwrap();
+	assert!(gpx.starts_with("<?xml"));
+	assert!(gpx.contains("<gpx version=\"1.1\""));
+	assert!(gpx.trim_end().ends_with("</gpx>"));
+	
+	let points = gpx.lines()
+		.filter(|line| line.trim_start().starts_with("<trkpt "))
+		.map(|line| {
+			let values = line.split('"').collect::<Vec<&str>>();
+			(values[1].parse::<f64>().unwrap(), values[3].parse::<f64>().unwrap())
+		})
+		.collect::<Vec<(f64, f64)>>();
+	
+	assert_eq!(path.len(), points.len());
+	for (point, &node_id) in points.iter().zip(path.iter()) {
+		assert_eq!(graph.node_coordinates(node_id).unwrap(), *point);
+	}
+	
+	assert!(graph.path_to_gpx(&[0, 5]).is_err());
+}
+
+#[test]
+fn test_to_dot() {
+	let graph = build_undirected_graph(&[(0.0, 0.0); 4],
+		&[(0, 1, 30, 50), (2, 1, 20, 50), (2, 3, 50, 50)]);
+	
+	let dot = graph.to_dot(10).unwrap();
+	assert!(dot.starts_with("graph {"));
+	assert!(dot.contains("  0 -- 1 [label=\"30\"];"));
+	assert!(dot.contains("  1 -- 2 [label=\"20\"];"));
+	assert!(dot.contains("  2 -- 3 [label=\"50\"];"));
+	assert_eq!(3, dot.matches(" -- ").count());
+	
+	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	let dot = graph.to_dot(5).unwrap();
+	assert!(dot.starts_with("digraph {"));
+	assert!(dot.contains("  3 -> 1 [label=\"40\"];"));
+	assert_eq!(6, dot.matches(" -> ").count());
+	
+	match graph.to_dot(4) {
+		Err(Error::FormatError { .. }) => { },
+		_ => { assert!(false); }
+	}
+}
+
+#[test]
+fn test_to_geojson() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	graph.set_arc_costs_to_travel_time(100);
+	
+	let json: serde_json::Value = serde_json::from_str(&graph.to_geojson()).unwrap();
+	assert_eq!("FeatureCollection", json["type"]);
+	
+	let features = json["features"].as_array().unwrap();
+	assert_eq!(5 + 6, features.len());
+	assert_eq!(5, features.iter().filter(|f| f["geometry"]["type"] == "Point").count());
+	assert_eq!(6, features.iter().filter(|f| f["geometry"]["type"] == "LineString").count());
+	
+	// Longitude first
+	assert_eq!(serde_json::json!([7.30089, 49.3418]), features[0]["geometry"]["coordinates"]);
+	assert_eq!(0, features[0]["properties"]["id"]);
+	
+	let arc = &features[5];
+	assert_eq!(serde_json::json!([[7.30089, 49.3418], [7.29997, 49.3406]]), arc["geometry"]["coordinates"]);
+	assert_eq!(3.6, arc["properties"]["costs"]);
+	assert_eq!(30, arc["properties"]["max_speed"]);
+}
+
+#[test]
+fn test_read_dimacs() {
+	let gr = "c Sample graph\np sp 3 4\na 1 2 30\na 2 1 30\na 2 3 20\na 3 1 70\n";
+	let co = "c Sample coordinates\np aux sp co 3\nv 1 7800000 48000000\nv 2 7800000 48100000\n\
+		v 3 -7900000 48100000\n";
+	
+	let mut graph = Graph::read_dimacs(Cursor::new(gr), Cursor::new(co)).unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(4, graph.num_arcs());
+	assert_eq!("[0->1(30), 1->0(30), 1->2(20), 2->0(70)]", format!("{}", graph));
+	assert_eq!(Some((48.1, -7.9)), graph.node_coordinates(2));
+	
+	assert_eq!(Some((50.0, vec![0, 1, 2])), graph.compute_shortest_path(0, 2).unwrap());
+	
+	// Arc to a missing node and missing coordinates
+	assert!(Graph::read_dimacs(Cursor::new("p sp 3 1\na 1 4 30\n"), Cursor::new(co)).is_err());
+	assert!(Graph::read_dimacs(Cursor::new(gr), Cursor::new("p aux sp co 3\nv 1 0 0\n")).is_err());
+}
+
+#[test]
+fn test_read_edge_list() {
+	let edges = "0,1,30\n1,3,20\n3,0, 50\n";
+	let mut graph = Graph::read_graph_from_edge_list(Cursor::new(edges),
+		DEFAULT_EDGE_LIST_MAX_SPEED).unwrap();
+	
+	assert_eq!(4, graph.num_nodes());
+	assert_eq!(3, graph.num_arcs());
+	assert_eq!(DEFAULT_EDGE_LIST_MAX_SPEED, graph.adjacency_lists[0][0].max_speed());
+	assert_eq!(Some((0.0, 0.0)), graph.node_coordinates(2));
+	
+	graph.read_coordinates_from_csv(Cursor::new("0,48.0,7.8\n3,48.1,7.9\n")).unwrap();
+	assert_eq!(Some((48.1, 7.9)), graph.node_coordinates(3));
+	assert!(graph.read_coordinates_from_csv(Cursor::new("4,48.0,7.8\n")).is_err());
+	
+	assert!(Graph::read_graph_from_edge_list(Cursor::new("0,1\n"), 50).is_err());
+}
+
+#[test]
+fn test_reversed() {
+	let graph = build_graph(&[(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)],
+		&[(0, 1, 30, 30), (0, 2, 70, 30), (1, 2, 20, 30), (2, 0, 50, 30)]);
+	
+	let reversed = graph.reversed();
+	assert_eq!(4, reversed.num_arcs());
+	assert_eq!(vec![(2, 50)], reversed.adjacency_lists[0].iter()
+		.map(|arc| (arc.head_node_id, arc.distance)).collect::<Vec<_>>());
+	assert_eq!(vec![(0, 70), (1, 20)], reversed.adjacency_lists[2].iter()
+		.map(|arc| (arc.head_node_id, arc.distance)).collect::<Vec<_>>());
+	
+	let twice = reversed.reversed();
+	for node_id in 0..graph.num_nodes() {
+		let arcs = |graph: &Graph| {
+			let mut arcs: Vec<_> = graph.adjacency_lists[node_id].iter()
+				.map(|arc| (arc.tail_node_id, arc.head_node_id, arc.distance, arc.max_speed))
+				.collect();
+			arcs.sort();
+			arcs
+		};
+		assert_eq!(arcs(&graph), arcs(&twice));
+	}
+	
+	let undirected = build_undirected_graph(&[(0.0, 0.0), (0.0, 1.0)], &[(0, 1, 30, 30)]);
+	assert_eq!(format!("{}", undirected), format!("{}", undirected.reversed()));
+}
+
+#[cfg(test)]
+fn build_random_graph(seed: u64, num_nodes: usize, num_arcs: usize) -> Graph {
+	// Linear congruential generator to keep the tests deterministic
+	let mut state = seed;
+	let mut next = |bound: u64| {
+		state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+		(state >> 33) % bound
+	};
+	
+	let mut graph = Graph::new();
+	for _ in 0..num_nodes {
+		graph.add_node(0.0, 0.0);
+	}
+	for _ in 0..num_arcs {
+		let tail_node = next(num_nodes as u64) as usize;
+		let head_node = next(num_nodes as u64) as usize;
+		let distance = next(100) + 1;
+		graph.add_arc(tail_node, head_node, distance, 30).unwrap();
+	}
+	
+	graph.set_arc_costs_to_distance();
+	graph
+}
+
+#[test]
+fn test_shortest_path_bidirectional() {
+	for seed in 0..5 {
+		let mut graph = build_random_graph(seed, 20, 50);
+		
+		for source in 0..graph.num_nodes() {
+			for target in 0..graph.num_nodes() {
+				let expected = graph.compute_shortest_path(source, target).unwrap();
+				let actual = graph.compute_shortest_path_bidirectional(source, target).unwrap();
+				
+				assert_eq!(expected.as_ref().map(|path| path.0), actual.as_ref().map(|path| path.0));
+				if let Some((_, path)) = actual {
+					assert_eq!(Some(&source), path.first());
+					assert_eq!(Some(&target), path.last());
+				}
+			}
+		}
+	}
+	
+	let mut graph = build_graph(&[(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)],
+		&[(0, 1, 30, 30), (1, 2, 20, 30), (0, 2, 70, 30)]);
+	assert_eq!(Some((50.0, vec![0, 1, 2])), graph.compute_shortest_path_bidirectional(0, 2).unwrap());
+	assert_eq!(None, graph.compute_shortest_path_bidirectional(2, 0).unwrap());
+	assert!(graph.compute_shortest_path_bidirectional(0, 3).is_err());
+	
+	// The incoming arcs are collected again after the arcs changed
+	graph.add_arc(2, 0, 10, 30).unwrap();
+	assert_eq!(Some((10.0, vec![2, 0])), graph.compute_shortest_path_bidirectional(2, 0).unwrap());
+	graph.set_arc_costs_with(|arc| if arc.head_node_id() == 2 { 1.0 } else { 5.0 }).unwrap();
+	assert_eq!(Some((1.0, vec![0, 2])), graph.compute_shortest_path_bidirectional(0, 2).unwrap());
+}
+
+#[test]
+fn test_minimum_spanning_tree() {
+	let graph = build_undirected_graph(&[(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0)],
+		&[(0, 1, 10, 30), (0, 2, 60, 30), (1, 2, 20, 30), (1, 3, 50, 30), (2, 3, 30, 30), (3, 3, 5, 30)]);
+	
+	let tree = graph.minimum_spanning_tree();
+	assert_eq!(vec![(0, 1, 10), (1, 2, 20), (2, 3, 30)], tree);
+	assert_eq!(60, spanning_tree_weight(&tree));
+	
+	// Spanning forest of a graph with two components
+	let graph = build_graph(&[(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)],
+		&[(0, 1, 10, 30), (1, 0, 20, 30)]);
+	assert_eq!(vec![(0, 1, 10)], graph.minimum_spanning_tree());
+}
+
+#[test]
+fn test_minimum_spanning_tree_prim() {
+	for seed in 0..5 {
+		let arcs: Vec<(usize, usize, u64, u64)> = build_random_graph(seed, 10, 30).edge_arcs().iter()
+			.map(|arc| (arc.tail_node_id, arc.head_node_id, arc.distance, arc.max_speed))
+			.collect();
+		let graph = build_undirected_graph(&[(0.0, 0.0); 10], &arcs);
+		
+		// Kruskal spans all components, Prim only the one of the start node
+		let components = graph.compute_components();
+		let kruskal: Vec<_> = graph.minimum_spanning_tree().into_iter()
+			.filter(|&(tail_node, _, _)| components[tail_node] == components[0]).collect();
+		let prim = graph.minimum_spanning_tree_prim(0).unwrap();
+		
+		assert_eq!(kruskal.len(), prim.len());
+		assert_eq!(spanning_tree_weight(&kruskal), spanning_tree_weight(&prim));
+	}
+	
+	let graph = build_undirected_graph(&[(0.0, 0.0); 4], &[(0, 1, 10, 30), (1, 2, 20, 30), (0, 2, 5, 30)]);
+	assert_eq!(vec![(0, 2, 5), (0, 1, 10)], graph.minimum_spanning_tree_prim(0).unwrap());
+	assert_eq!(Vec::<(usize, usize, u64)>::new(), graph.minimum_spanning_tree_prim(3).unwrap());
+	assert!(graph.minimum_spanning_tree_prim(4).is_err());
+}
+
+#[test]
+fn test_union_find() {
+	let mut sets = UnionFind::new(6);
+	assert!(!sets.same_set(0, 1));
+	
+	// Chained unions
+	assert!(sets.union(0, 1));
+	assert!(sets.union(1, 2));
+	assert!(sets.union(2, 3));
+	assert!(!sets.union(3, 0));
+	assert!(sets.same_set(0, 3));
+	assert!(!sets.same_set(0, 4));
+	
+	// Representatives are stable until the next union
+	let representative = sets.find(3);
+	for element in 0..4 {
+		assert_eq!(representative, sets.find(element));
+	}
+	assert_eq!(4, sets.find(4));
+	assert_eq!(representative, sets.find(2));
+	
+	assert!(sets.union(4, 5));
+	assert!(sets.union(5, 0));
+	assert!(sets.same_set(4, 1));
+	assert_eq!(sets.find(0), sets.find(5));
+}
+
+#[test]
+fn test_strongly_connected_components() {
+	// Cycle 0 -> 1 -> 2 -> 0 with the tail 2 -> 3 -> 4
+	let graph = build_graph(&[(0.0, 0.0); 5],
+		&[(0, 1, 10, 30), (1, 2, 10, 30), (2, 0, 10, 30), (2, 3, 10, 30), (3, 4, 10, 30)]);
+	
+	let components = graph.strongly_connected_components();
+	assert_eq!(vec![2, 2, 2, 1, 0], components);
+	
+	// Every arc leads to the same or an earlier component
+	for arcs in graph.adjacency_lists.iter() {
+		for arc in arcs.iter() {
+			assert!(components[arc.head_node_id] <= components[arc.tail_node_id]);
+		}
+	}
+	
+	// Deep path graph
+	let mut graph = Graph::new();
+	for node_id in 0..100000 {
+		graph.add_node(0.0, 0.0);
+		if node_id > 0 {
+			graph.add_arc(node_id - 1, node_id, 10, 30).unwrap();
+		}
+	}
+	graph.add_arc(99999, 0, 10, 30).unwrap();
+	assert!(graph.strongly_connected_components().iter().all(|&component| component == 0));
+}
+
+#[test]
+fn test_articulation_points_and_bridges() {
+	// Triangles 0-1-2 and 3-4-5 joined by the edge 2-3
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 6], &[(0, 1, 10, 30), (1, 2, 10, 30),
+		(2, 0, 10, 30), (2, 3, 10, 30), (3, 4, 10, 30), (4, 5, 10, 30), (5, 3, 10, 30)]);
+	
+	assert_eq!(vec![(2, 3)], graph.bridges());
+	assert_eq!(vec![2, 3], graph.articulation_points());
+	
+	// A parallel edge is not a bridge
+	graph.add_arc(3, 2, 10, 30).unwrap();
+	assert_eq!(Vec::<(usize, usize)>::new(), graph.bridges());
+	assert_eq!(vec![2, 3], graph.articulation_points());
+	
+	// Path 0 - 1 - 2
+	let graph = build_undirected_graph(&[(0.0, 0.0); 3], &[(1, 0, 10, 30), (1, 2, 10, 30)]);
+	assert_eq!(vec![(0, 1), (1, 2)], graph.bridges());
+	assert_eq!(vec![1], graph.articulation_points());
+}
+
+#[test]
+fn test_bfs() {
+	//       0
+	//     /   \
+	//    1     2
+	//   / \     \
+	//  3   4     5
+	let graph = build_graph(&[(0.0, 0.0); 7], &[(0, 2, 10, 30), (0, 1, 10, 30),
+		(1, 3, 10, 30), (1, 4, 10, 30), (2, 5, 10, 30), (4, 0, 10, 30)]);
+	
+	assert_eq!(vec![0, 2, 1, 5, 3, 4], graph.bfs(0).collect::<Vec<usize>>());
+	assert_eq!(vec![1, 3, 4, 0, 2, 5], graph.bfs(1).collect::<Vec<usize>>());
+	assert_eq!(vec![6], graph.bfs(6).collect::<Vec<usize>>());
+	assert!(graph.bfs(7).next().is_none());
+}
+
+#[test]
+fn test_estimate_diameter() {
+	// Undirected path 3 - 1 - 0 - 2 - 4 with a diameter of 100
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 5],
+		&[(3, 1, 40, 30), (1, 0, 30, 30), (0, 2, 20, 30), (2, 4, 10, 30)]);
+	graph.set_arc_costs_to_distance();
+	
+	assert_eq!(100.0, graph.estimate_diameter());
+	assert_eq!(0.0, Graph::new().estimate_diameter());
+}
 
-				if i + 1 != self.nodes.len() || j + 1 != lst.len() {
-					try!(write!(fmt, ", "));
-				}
+#[test]
+fn test_cost_matrix() {
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 5],
+		&[(0, 1, 30, 30), (1, 2, 20, 30), (0, 2, 70, 30), (2, 3, 10, 30)]);
+	graph.set_arc_costs_to_distance();
+	
+	let matrix = graph.cost_matrix(&[0, 3, 4]);
+	assert_eq!(vec![
+		vec![Some(0.0), Some(60.0), None],
+		vec![Some(60.0), Some(0.0), None],
+		vec![None, None, Some(0.0)]
+	], matrix);
+	
+	for i in 0..3 {
+		for j in 0..3 {
+			assert_eq!(matrix[i][j], matrix[j][i]);
+		}
+	}
+}
+
+#[test]
+fn test_k_shortest_paths() {
+	// 0 - 1 - 2
+	// |   |   |
+	// 3 - 4 - 5
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 6], &[(0, 1, 10, 30), (1, 2, 10, 30),
+		(0, 3, 15, 30), (3, 4, 15, 30), (4, 5, 15, 30), (1, 4, 5, 30), (2, 5, 12, 30)]);
+	graph.set_arc_costs_to_distance();
+	
+	assert_eq!(vec![(30.0, vec![0, 1, 4, 5]), (32.0, vec![0, 1, 2, 5])],
+		graph.k_shortest_paths(0, 5, 2).unwrap());
+	
+	// There are only four loopless paths
+	let paths = graph.k_shortest_paths(0, 5, 10).unwrap();
+	assert_eq!(vec![30.0, 32.0, 45.0, 57.0], paths.iter().map(|path| path.0).collect::<Vec<f64>>());
+	assert_eq!(vec![0, 3, 4, 1, 2, 5], paths[3].1);
+	
+	// The blocked arcs are restored
+	assert_eq!(Some((30.0, vec![0, 1, 4, 5])), graph.compute_shortest_path(0, 5).unwrap());
+	
+	graph.add_node(0.0, 0.0);
+	assert_eq!(Vec::<(f64, Vec<usize>)>::new(), graph.k_shortest_paths(0, 6, 3).unwrap());
+	assert_eq!(vec![(0.0, vec![0])], graph.k_shortest_paths(0, 0, 3).unwrap());
+	assert!(graph.k_shortest_paths(0, 7, 3).is_err());
+	assert!(graph.k_shortest_paths(7, 0, 0).is_err());
+}
+
+#[test]
+fn test_costs_overflow() {
+	let mut graph = build_graph(&[(0.0, 0.0); 3], &[(0, 1, 10, 30), (1, 2, 10, 30)]);
+	graph.set_arc_costs_with(|_| f64::MAX).unwrap();
+	
+	// The costs of 0 -> 2 overflow and must not be reported as a path
+	assert_eq!(Some((f64::MAX, vec![0, 1])), graph.compute_shortest_path(0, 1).unwrap());
+	assert_eq!(None, graph.compute_shortest_path(0, 2).unwrap());
+	assert_eq!(None, graph.compute_shortest_path_bidirectional(0, 2).unwrap());
+	assert_eq!(vec![Some(0.0), Some(f64::MAX), None], graph.to_csr().compute_shortest_paths(0).unwrap().0);
+	
+	graph.set_arc_costs_with(|_| f64::INFINITY).unwrap();
+	assert_eq!(None, graph.compute_shortest_path(0, 1).unwrap());
+}
+
+#[test]
+fn test_bellman_ford() {
+	// Negative arc 2 -> 1 without a negative cycle
+	let mut graph = build_graph(&[(0.0, 0.0); 4],
+		&[(0, 1, 40, 30), (0, 2, 10, 30), (2, 1, 0, 30), (1, 2, 50, 30)]);
+	let costs = |arc: &Arc| if arc.distance() == 0 { -20 } else { arc.distance() as i64 };
+	
+	assert_eq!(vec![Some(0), Some(-10), Some(10), None],
+		graph.compute_shortest_path_bellman_ford(0, costs).unwrap());
+	assert!(graph.compute_shortest_path_bellman_ford(4, costs).is_err());
+	
+	// Negative cycle 1 -> 2 -> 1 with costs of -5
+	graph.add_arc(2, 1, 0, 30).unwrap();
+	let costs = |arc: &Arc| match (arc.tail_node_id(), arc.head_node_id()) {
+		(1, 2) => 15,
+		(2, 1) => -20,
+		_ => arc.distance() as i64
+	};
+	assert!(graph.compute_shortest_path_bellman_ford(0, costs).is_err());
+	
+	// The cycle is not reachable from node 3
+	assert_eq!(vec![None, None, None, Some(0)],
+		graph.compute_shortest_path_bellman_ford(3, costs).unwrap());
+}
+
+#[test]
+fn test_induced_subgraph() {
+	let graph = build_graph(&[(0.0, 0.0), (0.0, 1.0), (0.0, 2.0), (0.0, 3.0), (0.0, 4.0), (0.0, 5.0)],
+		&[(0, 1, 10, 30), (1, 2, 20, 30), (2, 3, 30, 30), (3, 4, 40, 30), (4, 5, 50, 30),
+			(5, 1, 60, 30), (4, 1, 70, 30)]);
+	
+	let (subgraph, original_ids) = graph.induced_subgraph(&[4, 1, 2, 4]).unwrap();
+	assert_eq!(vec![4, 1, 2], original_ids);
+	assert_eq!(3, subgraph.num_nodes());
+	assert_eq!(Some((0.0, 4.0)), subgraph.node_coordinates(0));
+	
+	// Only 1 -> 2 and 4 -> 1 remain, the arcs to 0, 3 and 5 are dropped
+	assert_eq!(2, subgraph.num_arcs());
+	assert_eq!(vec![(1, 70)], subgraph.adjacency_lists[0].iter()
+		.map(|arc| (arc.head_node_id(), arc.distance())).collect::<Vec<_>>());
+	assert_eq!(vec![(2, 20)], subgraph.adjacency_lists[1].iter()
+		.map(|arc| (arc.head_node_id(), arc.distance())).collect::<Vec<_>>());
+	assert_eq!(Some(4), subgraph.original_id(0));
+	assert_eq!(Some(2), subgraph.original_id(2));
+	
+	assert!(graph.induced_subgraph(&[1, 6]).is_err());
+	
+	// The file ids of sparse graphs are kept
+	let data = "3\n2\n30 48.0 7.8\n10 48.1 7.8\n20 48.2 7.8\n10 20 100 50\n30 10 300 50\n";
+	let options = GraphBuildOptions { sparse_ids: true, ..Default::default() };
+	let graph = Graph::read_graph_from_reader_with_options(Cursor::new(data), options).unwrap();
+	let (subgraph, original_ids) = graph.induced_subgraph(&[2, 1]).unwrap();
+	assert_eq!(vec![2, 1], original_ids);
+	assert_eq!(Some(20), subgraph.original_id(0));
+	assert_eq!(Some(10), subgraph.original_id(1));
+	assert_eq!(None, subgraph.original_id(2));
+}
+
+#[test]
+fn test_bbox() {
+	let graph = build_graph(&[(48.0, 7.8), (48.1, 7.9), (47.0, 7.85), (48.05, 179.5),
+		(48.05, -179.5), (48.05, 0.0)], &[(0, 1, 10, 30), (1, 2, 20, 30), (3, 4, 30, 30)]);
+	
+	assert_eq!(vec![0, 1], graph.nodes_in_bbox(47.5, 7.0, 48.5, 8.0));
+	assert_eq!(vec![3, 4], graph.nodes_in_bbox(47.5, 179.0, 48.5, -179.0));
+	assert_eq!(Vec::<usize>::new(), graph.nodes_in_bbox(49.0, 7.0, 50.0, 8.0));
+	
+	let (subgraph, original_ids) = graph.subgraph_in_bbox(47.5, 7.0, 48.5, 8.0);
+	assert_eq!(vec![0, 1], original_ids);
+	assert_eq!(1, subgraph.num_arcs());
+	
+	let (subgraph, original_ids) = graph.subgraph_in_bbox(47.5, 179.0, 48.5, -179.0);
+	assert_eq!(vec![3, 4], original_ids);
+	assert_eq!(1, subgraph.num_arcs());
+}
+
+#[test]
+fn test_deduplicate_arcs() {
+	let mut graph = build_graph(&[(0.0, 0.0); 3], &[(0, 1, 50, 30), (0, 2, 10, 30),
+		(0, 1, 30, 30), (0, 1, 70, 30), (1, 0, 40, 30)]);
+	graph.set_arc_costs_with(|arc| arc.distance() as f64 / 10.0).unwrap();
+	
+	graph.deduplicate_arcs();
+	assert_eq!(3, graph.num_arcs());
+	assert_eq!(vec![(1, 3.0, 30), (2, 1.0, 10)], graph.adjacency_lists[0].iter()
+		.map(|arc| (arc.head_node_id(), arc.costs(), arc.distance())).collect::<Vec<_>>());
+	assert_eq!(Some((3.0, vec![0, 1])), graph.compute_shortest_path(0, 1).unwrap());
+}
+
+#[test]
+fn test_remove_self_loops() {
+	let mut graph = build_graph(&[(0.0, 0.0); 2], &[(0, 0, 10, 30), (0, 1, 20, 30), (1, 1, 30, 30)]);
+	assert_eq!(2, graph.remove_self_loops());
+	assert_eq!(1, graph.num_arcs());
+	assert_eq!(0, graph.remove_self_loops());
+	
+	let mut graph = build_undirected_graph(&[(0.0, 0.0), (0.0, 1.0)],
+		&[(0, 0, 10, 30), (0, 1, 20, 30), (1, 1, 30, 30)]);
+	assert_eq!(6, graph.num_arcs());
+	
+	assert_eq!(2, graph.remove_self_loops());
+	assert_eq!(2, graph.num_arcs());
+	assert_eq!(vec![1], graph.neighbors(0).unwrap().map(|(node_id, _)| node_id).collect::<Vec<_>>());
+	assert_eq!(vec![0], graph.neighbors(1).unwrap().map(|(node_id, _)| node_id).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_degree_histogram() {
+	// Star with the center 0 and five leaves, node 6 is isolated
+	let graph = build_undirected_graph(&[(0.0, 0.0); 7],
+		&[(0, 1, 10, 30), (0, 2, 10, 30), (0, 3, 10, 30), (0, 4, 10, 30), (0, 5, 10, 30)]);
+	
+	assert_eq!(vec![(0, 1), (1, 5), (5, 1)], graph.degree_histogram());
+	assert_eq!(Vec::<(usize, usize)>::new(), Graph::new().degree_histogram());
+}
+
+#[test]
+fn test_stats() {
+	// Triangle 0-1-2, edge 3-4 and the isolated node 5
+	let graph = build_undirected_graph(&[(0.0, 0.0); 6],
+		&[(0, 1, 10, 30), (1, 2, 20, 30), (2, 0, 30, 30), (3, 4, 40, 30)]);
+	
+	assert_eq!(GraphStats {
+		num_nodes: 6,
+		num_arcs: 8,
+		num_components: 3,
+		largest_component_size: 3,
+		min_degree: 0,
+		max_degree: 2,
+		mean_degree: 8.0 / 6.0,
+		total_arc_length: 100
+	}, graph.stats());
+	
+	let stats = Graph::new().stats();
+	assert_eq!((0, 0, 0, 0.0), (stats.num_nodes, stats.num_components, stats.max_degree,
+		stats.mean_degree));
+}
+
+#[test]
+fn test_pagerank() {
+	// Hub 0 connected to 1, 2 and 3 with the additional edge 1-2 and the
+	// dangling node 4
+	let graph = build_undirected_graph(&[(0.0, 0.0); 5],
+		&[(0, 1, 10, 30), (0, 2, 10, 30), (0, 3, 10, 30), (1, 2, 10, 30)]);
+	
+	let scores = graph.pagerank(0.85, 50);
+	assert!((scores.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+	for node_id in 1..5 {
+		assert!(scores[0] > scores[node_id]);
+	}
+	assert!((scores[1] - scores[2]).abs() < 1e-9);
+	assert!(scores[1] > scores[3]);
+	assert!(scores[3] > scores[4]);
+	
+	assert_eq!(Vec::<f64>::new(), Graph::new().pagerank(0.85, 50));
+}
+
+#[test]
+fn test_closeness_centrality() {
+	// Path 0 - 1 - 2 - 3 - 4 and the separate edge 5 - 6
+	let mut graph = build_undirected_graph(&[(0.0, 0.0); 8],
+		&[(0, 1, 10, 30), (1, 2, 10, 30), (2, 3, 10, 30), (3, 4, 10, 30), (5, 6, 10, 30)]);
+	graph.set_arc_costs_to_distance();
+	
+	assert_eq!(4.0 / 60.0, graph.closeness_centrality(2).unwrap());
+	assert_eq!(4.0 / 100.0, graph.closeness_centrality(0).unwrap());
+	assert_eq!(1.0 / 10.0, graph.closeness_centrality(5).unwrap());
+	assert_eq!(0.0, graph.closeness_centrality(7).unwrap());
+	assert!(graph.closeness_centrality(8).is_err());
+	
+	assert_eq!(Some(2), graph.most_central_node());
+	assert_eq!(None, Graph::new().most_central_node());
+}
+
+#[test]
+fn test_greedy_coloring() {
+	let triangle = build_undirected_graph(&[(0.0, 0.0); 3],
+		&[(0, 1, 10, 30), (1, 2, 10, 30), (2, 0, 10, 30)]);
+	assert_eq!(vec![0, 1, 2], triangle.greedy_coloring());
+	assert_eq!(3, triangle.chromatic_number_upper_bound());
+	
+	let path = build_undirected_graph(&[(0.0, 0.0); 5],
+		&[(0, 1, 10, 30), (1, 2, 10, 30), (2, 3, 10, 30), (3, 4, 10, 30)]);
+	let colors = path.greedy_coloring();
+	for node_id in 0..4 {
+		assert!(colors[node_id] != colors[node_id + 1]);
+	}
+	assert_eq!(2, path.chromatic_number_upper_bound());
+	
+	assert_eq!(0, Graph::new().chromatic_number_upper_bound());
+}
+
+#[test]
+fn test_two_coloring() {
+	// Even cycle and a separate edge
+	let graph = build_undirected_graph(&[(0.0, 0.0); 6],
+		&[(0, 1, 10, 30), (1, 2, 10, 30), (2, 3, 10, 30), (3, 0, 10, 30), (4, 5, 10, 30)]);
+	assert!(graph.is_bipartite());
+	assert_eq!(Some(vec![0, 1, 0, 1, 0, 1]), graph.two_coloring());
+	
+	// Odd cycle in the second component
+	let graph = build_undirected_graph(&[(0.0, 0.0); 5],
+		&[(0, 1, 10, 30), (2, 3, 10, 30), (3, 4, 10, 30), (4, 2, 10, 30)]);
+	assert!(!graph.is_bipartite());
+	assert_eq!(None, graph.two_coloring());
+	
+	assert!(!build_undirected_graph(&[(0.0, 0.0)], &[(0, 0, 10, 30)]).is_bipartite());
+}
+
+#[test]
+fn test_shortest_path_alt() {
+	for seed in 0..5 {
+		let mut graph = build_random_graph(seed, 30, 90);
+		graph.precompute_landmarks(4);
+		assert_eq!(4, graph.landmarks.len());
+		
+		for source in 0..graph.num_nodes() {
+			for target in 0..graph.num_nodes() {
+				let expected = graph.compute_shortest_path(source, target).unwrap();
+				let actual = graph.compute_shortest_path_alt(source, target).unwrap();
+				assert_eq!(expected.map(|path| path.0), actual.map(|path| path.0));
 			}
 		}
-		try!(write!(fmt, "]"));
+	}
+	
+	// The landmarks reduce the number of settled nodes on a grid
+	let size = 30;
+	let mut arcs = Vec::new();
+	for node_id in 0..size * size {
+		if node_id % size + 1 < size {
+			arcs.push((node_id, node_id + 1, 10 + (node_id % 7) as u64, 30));
+		}
+		if node_id + size < size * size {
+			arcs.push((node_id, node_id + size, 10 + (node_id % 5) as u64, 30));
+		}
+	}
+	let mut graph = build_undirected_graph(&vec![(0.0, 0.0); size * size], &arcs);
+	graph.set_arc_costs_to_distance();
+	graph.precompute_landmarks(4);
+	
+	let settled = |graph: &Graph| graph.search_state.settled.iter().filter(|&&settled| settled).count();
+	let expected = graph.compute_shortest_path(size / 2, size * size - size / 2).unwrap();
+	let dijkstra_settled = settled(&graph);
+	let actual = graph.compute_shortest_path_alt(size / 2, size * size - size / 2).unwrap();
+	let alt_settled = settled(&graph);
+	
+	assert_eq!(expected, actual);
+	assert!(alt_settled < dijkstra_settled / 2);
+	assert!(Graph::new().compute_shortest_path_alt(0, 0).is_err());
+}
 
-		return Ok(());
+#[test]
+fn test_parallel_queries() {
+	let mut graph = build_random_graph(3, 40, 160);
+	let expected: Vec<Vec<Option<f64>>> = (0..graph.num_nodes())
+		.map(|source| (0..graph.num_nodes())
+			.map(|target| graph.compute_shortest_path(source, target).unwrap().map(|path| path.0))
+			.collect())
+		.collect();
+	
+	let graph = SharedArc::new(graph);
+	let threads: Vec<_> = (0..4).map(|thread_id| {
+		let graph = graph.clone();
+		thread::spawn(move || {
+			let mut state = SearchState::new(&graph);
+			let mut results = Vec::new();
+			
+			for source in (thread_id..graph.num_nodes()).step_by(4) {
+				let costs = (0..graph.num_nodes()).map(|target| {
+					graph.compute_shortest_path_with_state(&mut state, source, target).unwrap()
+						.map(|path| path.0)
+				}).collect::<Vec<Option<f64>>>();
+				results.push((source, costs));
+			}
+			
+			results
+		})
+	}).collect();
+	
+	for thread in threads {
+		for (source, costs) in thread.join().unwrap() {
+			assert_eq!(expected[source], costs);
+		}
 	}
+	
+	let mut state = SearchState::new(&graph);
+	graph.compute_shortest_paths_with_state(&mut state, 0).unwrap();
+	for target in 0..graph.num_nodes() {
+		assert_eq!(expected[0][target], state.distance(target));
+		assert_eq!(state.distance(target).is_some(), state.is_settled(target));
+	}
+	assert!(graph.compute_shortest_paths_with_state(&mut state, 40).is_err());
 }
 
+#[cfg(feature = "rayon")]
 #[test]
-fn test_read() {
+fn test_components_parallel() {
+	for seed in 0..5 {
+		let arcs: Vec<(usize, usize, u64, u64)> = build_random_graph(seed, 200, 150).edge_arcs().iter()
+			.map(|arc| (arc.tail_node_id, arc.head_node_id, arc.distance, arc.max_speed))
+			.collect();
+		let graph = build_undirected_graph(&[(0.0, 0.0); 200], &arcs);
+		
+		assert_eq!(graph.compute_components(), graph.compute_components_parallel());
+	}
+	
+	assert_eq!(Vec::<usize>::new(), Graph::new().compute_components_parallel());
+}
+
+#[test]
+fn test_read_progress() {
+	let mut calls = Vec::new();
+	let graph = Graph::read_graph_from_file_with_progress("graphs/test.zip",
+		&mut |lines, total| calls.push((lines, total))).unwrap();
+	
+	assert_eq!(5, graph.num_nodes());
+	assert_eq!(Some(&(13, 13)), calls.last());
+}
+
+#[test]
+fn test_iter_nodes_and_arcs() {
 	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	
+	let nodes: Vec<(usize, f64, f64)> = graph.iter_nodes().collect();
+	assert_eq!(graph.num_nodes(), nodes.len());
+	assert_eq!((0..5).collect::<Vec<usize>>(), nodes.iter().map(|node| node.0).collect::<Vec<usize>>());
+	assert_eq!(graph.node_coordinates(3), Some((nodes[3].1, nodes[3].2)));
+	
+	let arcs: Vec<(usize, usize, u64, u64)> = graph.iter_arcs().collect();
+	assert_eq!(graph.num_arcs(), arcs.len());
+	assert_eq!((2, 3, 50, 30), arcs[3]);
+}
 
-	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
-		format!("{}", graph));
+#[test]
+fn test_path_summary() {
+	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	graph.set_arc_costs_to_distance();
+	
+	let (_, path) = graph.compute_shortest_path(0, 3).unwrap().unwrap();
+	assert_eq!(vec![0, 1, 2, 3], path);
+	
+	let summary = graph.path_summary(&path, 30).unwrap();
+	assert_eq!(100.0, summary.distance);
+	assert_eq!(100.0 * 3.6 / 30.0, summary.travel_time);
+	assert_eq!(3, summary.num_arcs);
+	assert_eq!(0, summary.num_turns);
+	
+	// Nodes 1 and 2 have three arcs in the undirected graph
+	let graph = Graph::read_graph_from_file_with_options("graphs/test.zip",
+		GraphBuildOptions { directed: false, ..Default::default() }).unwrap();
+	assert_eq!(2, graph.path_summary(&path, 30).unwrap().num_turns);
+	assert_eq!(1, graph.path_summary(&[0, 2, 3], 30).unwrap().num_turns);
+	
+	assert_eq!(0, graph.path_summary(&[1], 30).unwrap().num_arcs);
+	assert!(graph.path_summary(&[0, 4], 30).is_err());
+	assert!(graph.path_summary(&[0, 5], 30).is_err());
+	
+	// A speed of zero gives an infinite travel time, also on arcs of length zero
+	let graph = build_graph(&[(0.0, 0.0); 2], &[(0, 1, 0, 50)]);
+	assert_eq!(f64::INFINITY, graph.path_summary(&[0, 1], 0).unwrap().travel_time);
 }
 
 #[test]
-fn test_reachable_nodes() {
-	let graph = Graph::read_graph_from_file("graphs/test2.zip").unwrap();
+fn test_costs_for_profile() {
+	let mut graph = build_graph(&[(0.0, 0.0); 3], &[(0, 1, 1000, 50), (1, 2, 1000, 10)]);
+	
+	graph.set_costs_for_profile(Profile::Car(130));
+	let car_costs = vec![graph.adjacency_lists[0][0].costs(), graph.adjacency_lists[1][0].costs()];
+	assert_eq!(vec![72.0, 360.0], car_costs);
+	
+	graph.set_costs_for_profile(Profile::Bicycle);
+	assert_eq!(240.0, graph.adjacency_lists[0][0].costs());
+	assert_eq!(240.0, graph.adjacency_lists[1][0].costs());
+	
+	graph.set_costs_for_profile(Profile::Pedestrian);
+	assert_eq!(720.0, graph.adjacency_lists[0][0].costs());
+	assert!(graph.adjacency_lists[0][0].costs() > car_costs[0]);
+	assert!(graph.adjacency_lists[1][0].costs() > car_costs[1]);
+}
 
-	assert_eq!(4, graph.compute_reachable_nodes(0).0);
-	assert_eq!(6, graph.compute_reachable_nodes(4).0);
-	assert_eq!(1, graph.compute_reachable_nodes(6).0);
+#[test]
+fn test_read_distance_units() {
+	let meters = "3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.2 7.8\n0 1 1500 50\n1 2 250 30\n";
+	let kilometers = "3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.2 7.8\n0 1 1.5 50\n1 2 0.25 30\n";
+	let decimeters = "3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.2 7.8\n0 1 15000 50\n1 2 2500 30\n";
+	
+	let read = |data: &str, unit: DistanceUnit| {
+		let mut graph = Graph::with_options(GraphBuildOptions { distance_unit: unit, ..Default::default() });
+		graph.read_lines(Cursor::new(data), None, None).unwrap();
+		graph.set_arc_costs_to_travel_time(130);
+		graph
+	};
+	
+	let expected = read(meters, DistanceUnit::Meters);
+	assert_eq!(DistanceUnit::Meters, expected.distance_unit());
+	for &(data, unit) in [(kilometers, DistanceUnit::Kilometers), (decimeters, DistanceUnit::Decimeters)].iter() {
+		let graph = read(data, unit);
+		assert_eq!(unit, graph.distance_unit());
+		for node_id in 0..2 {
+			assert_eq!(expected.adjacency_lists[node_id][0].distance, graph.adjacency_lists[node_id][0].distance);
+			assert_eq!(expected.adjacency_lists[node_id][0].costs(), graph.adjacency_lists[node_id][0].costs());
+		}
+	}
+	
+	let mut graph = Graph::with_options(GraphBuildOptions { distance_unit: DistanceUnit::Kilometers, ..Default::default() });
+	let data = "2\n1\n0 48.0 7.8\n1 48.1 7.8\n0 1 -1.5 50\n";
+	assert!(graph.read_lines(Cursor::new(data), None, None).is_err());
 }
 
 #[test]
-fn test_shortest() {
-	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+fn test_read_duplicate_node_id() {
+	let data = "3\n1\n0 48.0 7.8\n0 48.1 7.8\n2 48.1 7.9\n0 2 30 50\n";
+	
+	match Graph::read_graph_from_reader(Cursor::new(data)).err().unwrap() {
+		Error::FormatError { ref message } => {
+			assert!(message.contains("Duplicate node id 0"));
+			assert!(message.contains("line 4"));
+		},
+		_ => { assert!(false); }
+	}
+	
+	let data = "2\n0\n0 48.0 7.8\n5 48.1 7.8\n";
+	assert!(Graph::read_graph_from_reader(Cursor::new(data)).is_err());
+	
+	// Node lines cannot be skipped, the following ids would be shifted
+	let data = "3\n2\n0 48.0 7.8\n1 48.1\n2 48.1 7.9\n0 1 10 50\n0 2 20 50\n";
+	assert!(Graph::read_graph_from_reader_lenient(Cursor::new(data)).is_err());
+	let data = "3\n2\n0 48.0 7.8\n0 48.1 7.8\n2 48.1 7.9\n0 1 10 50\n0 2 20 50\n";
+	assert!(Graph::read_graph_from_reader_lenient(Cursor::new(data)).is_err());
+	
+	// Arcs after a skipped arc line still reach the nodes of the file
+	let data = "3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.1 7.9\n0 1 x 50\n0 2 20 50\n";
+	let (graph, errors) = Graph::read_graph_from_reader_lenient(Cursor::new(data)).unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(1, errors.len());
+	assert_eq!(1, graph.adjacency_lists[0].len());
+	assert_eq!(2, graph.adjacency_lists[0][0].head_node_id);
+	assert_eq!(Some((48.1, 7.9)), graph.node_coordinates(2));
+}
 
-	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
-		format!("{}", graph));
+#[test]
+fn test_read_sparse_ids() {
+	let data = "3\n3\n30 48.0 7.8\n10 48.1 7.8\n20 48.2 7.8\n10 20 100 50\n20 30 200 50\n30 10 300 50\n";
+	let options = GraphBuildOptions { sparse_ids: true, ..Default::default() };
+	
+	let graph = Graph::read_graph_from_reader_with_options(Cursor::new(data), options).unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(Some(30), graph.original_id(0));
+	assert_eq!(Some(10), graph.original_id(1));
+	assert_eq!(Some(20), graph.original_id(2));
+	assert_eq!(None, graph.original_id(3));
+	assert_eq!("[0->1(300), 1->2(100), 2->0(200)]", format!("{}", graph));
+	
+	// Dense ids are rejected without the sparse mode
+	assert!(Graph::read_graph_from_reader(Cursor::new(data)).is_err());
+	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	assert_eq!(Some(3), graph.original_id(3));
+	
+	let data = "2\n1\n10 48.0 7.8\n10 48.1 7.8\n10 10 100 50\n";
+	assert!(Graph::read_graph_from_reader_with_options(Cursor::new(data), options).is_err());
+	let data = "2\n1\n10 48.0 7.8\n20 48.1 7.8\n10 15 100 50\n";
+	assert!(Graph::read_graph_from_reader_with_options(Cursor::new(data), options).is_err());
+}
 
-	graph.compute_shortest_paths(1);
+#[test]
+fn test_route_between_coordinates() {
+	let mut graph = build_graph(&[(48.000, 7.800), (48.001, 7.800), (48.002, 7.800), (48.002, 7.801)],
+		&[(0, 1, 111, 50), (1, 2, 111, 50), (0, 3, 500, 50), (3, 2, 74, 50)]);
+	
+	let (costs, path) = graph.route_between_coordinates((48.0001, 7.8), (48.0021, 7.8)).unwrap().unwrap();
+	assert_eq!(222.0, costs);
+	assert_eq!(vec![0, 1, 2], path);
+	
+	assert_eq!(None, graph.route_between_coordinates((48.002, 7.8), (48.0, 7.8)).unwrap());
+	
+	match graph.route_between_coordinates((48.0, 7.8), (49.0, 7.8)) {
+		Err(Error::FormatError { message }) => { assert!(message.contains("No node")); },
+		_ => { assert!(false); }
+	}
+	assert!(Graph::new().route_between_coordinates((48.0, 7.8), (48.0, 7.8)).is_err());
+}
 
-	let expected = vec![None, Some(0.0), Some(20.0), Some(70.0), None];
-	assert_eq!(expected,
-		graph.nodes.iter().map(|node| node.distance).collect::<Vec<Option<f64>>>());
+#[test]
+fn test_weighted_degree() {
+	let mut graph = build_undirected_graph(&[(48.0, 7.8); 4],
+		&[(0, 1, 3, 50), (0, 2, 4, 50), (3, 0, 5, 50), (1, 2, 7, 50)]);
+	
+	assert_eq!(12.0, graph.weighted_degree(0).unwrap());
+	assert_eq!(10.0, graph.weighted_degree(1).unwrap());
+	assert_eq!(5.0, graph.weighted_degree(3).unwrap());
+	assert!(graph.weighted_degree(4).is_err());
+	
+	// The mirrored arcs are not counted twice
+	assert_eq!(19.0, graph.total_graph_cost());
+	
+	graph.set_arc_costs_to_travel_time(36);
+	assert!((graph.total_graph_cost() - 1.9).abs() < 1e-9);
+	
+	let graph = build_graph(&[(0.0, 0.0); 3], &[(0, 1, 3, 50), (1, 0, 4, 50), (0, 2, 5, 50)]);
+	assert_eq!(8.0, graph.weighted_degree(0).unwrap());
+	assert_eq!(12.0, graph.total_graph_cost());
 }
 
 #[test]
-fn test_costs_distance() {
-	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+fn test_arc_cost() {
+	let graph = build_graph(&[(0.0, 0.0); 4], &[(0, 1, 30, 50), (0, 1, 20, 50), (1, 2, 10, 50), (0, 1, 25, 50)]);
+	
+	assert_eq!(Some(20.0), graph.arc_cost(0, 1));
+	assert_eq!(Some(10.0), graph.arc_cost(1, 2));
+	assert_eq!(None, graph.arc_cost(1, 0));
+	assert_eq!(None, graph.arc_cost(0, 2));
+	assert_eq!(None, graph.arc_cost(3, 0));
+	assert_eq!(None, graph.arc_cost(4, 0));
+	
+	let graph = Graph::read_graph_from_file_with_options("graphs/test.zip",
+		GraphBuildOptions { directed: false, ..Default::default() }).unwrap();
+	assert_eq!(graph.arc_cost(0, 1), graph.arc_cost(1, 0));
+}
 
-	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
-		format!("{}", graph));
+#[test]
+fn test_remove_isolated_nodes() {
+	let mut graph = build_graph(&[(48.0, 7.8), (48.1, 7.8), (48.2, 7.8)], &[(0, 2, 30, 50)]);
+	
+	assert_eq!(vec![1], graph.isolated_nodes());
+	assert_eq!(1, graph.remove_isolated_nodes());
+	
+	assert_eq!(2, graph.num_nodes());
+	assert_eq!(Some((48.2, 7.8)), graph.node_coordinates(1));
+	assert_eq!(1, graph.adjacency_lists[0][0].head_node_id);
+	assert!(graph.isolated_nodes().is_empty());
+	assert_eq!(0, graph.remove_isolated_nodes());
+	
+	let (costs, path) = graph.compute_shortest_path(0, 1).unwrap().unwrap();
+	assert_eq!(30.0, costs);
+	assert_eq!(vec![0, 1], path);
+	
+	// Sparse ids are kept for the remaining nodes
+	let data = "3\n1\n30 48.0 7.8\n10 48.1 7.8\n20 48.2 7.8\n20 30 100 50\n";
+	let mut graph = Graph::read_graph_from_reader_with_options(Cursor::new(data),
+		GraphBuildOptions { sparse_ids: true, ..Default::default() }).unwrap();
+	assert_eq!(1, graph.remove_isolated_nodes());
+	assert_eq!(Some(30), graph.original_id(0));
+	assert_eq!(Some(20), graph.original_id(1));
+}
 
-	graph.set_arc_costs_to_travel_time(100);
-	graph.set_arc_costs_to_distance();
+#[test]
+fn test_read_truncated() {
+	let data = "5\n1\n0 48.0 7.8\n1 48.1 7.8\n2 48.1 7.9\n3 48.2 7.9\n";
+	match Graph::read_graph_from_reader(Cursor::new(data)) {
+		Err(Error::FormatError { message }) => {
+			assert!(message.contains("Expected 5 nodes and 1 arcs, found 4 nodes and 0 arcs"));
+		},
+		_ => { assert!(false); }
+	}
+	
+	let data = "2\n3\n0 48.0 7.8\n1 48.1 7.8\n0 1 30 50\n1 0 30 50\n";
+	match Graph::read_graph_from_reader(Cursor::new(data)) {
+		Err(Error::FormatError { message }) => { assert!(message.contains("found 2 nodes and 2 arcs")); },
+		_ => { assert!(false); }
+	}
+	
+	// The lenient reader keeps the lines which are present
+	let (graph, errors) = Graph::read_graph_from_reader_lenient(Cursor::new(data)).unwrap();
+	assert_eq!(2, graph.num_arcs());
+	assert_eq!(1, errors.len());
+	
+	assert!(Graph::read_graph_from_reader(Cursor::new("")).is_err());
+}
 
-	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
-		format!("{}", graph));
+#[test]
+fn test_dfs_times() {
+	// Tree with the root 0, its children 1 and 4 and the leaves 2, 3 and 5
+	let graph = build_graph(&[(0.0, 0.0); 7],
+		&[(0, 1, 1, 50), (1, 2, 1, 50), (1, 3, 1, 50), (0, 4, 1, 50), (4, 5, 1, 50)]);
+	let (discovery_times, finish_times) = graph.dfs_times(0).unwrap();
+	
+	assert_eq!(vec![Some(0), Some(1), Some(2), Some(4), Some(7), Some(8), None], discovery_times);
+	assert_eq!(vec![Some(11), Some(6), Some(3), Some(5), Some(10), Some(9), None], finish_times);
+	
+	// The intervals of a node and its descendants are nested, other
+	// intervals are disjoint
+	let interval = |node_id: usize| (discovery_times[node_id].unwrap(), finish_times[node_id].unwrap());
+	let is_ancestor = |ancestor: usize, node_id: usize| match (ancestor, node_id) {
+		(0, _) => true,
+		(1, 2) | (1, 3) | (4, 5) => true,
+		(a, b) => a == b
+	};
+	for u in 0..6 {
+		for v in 0..6 {
+			let (u_start, u_end) = interval(u);
+			let (v_start, v_end) = interval(v);
+			if is_ancestor(u, v) {
+				assert!(u_start <= v_start && v_end <= u_end);
+			} else if !is_ancestor(v, u) {
+				assert!(u_end < v_start || v_end < u_start);
+			}
+		}
+	}
+	
+	// A long path does not overflow the call stack
+	let num_nodes = 200000;
+	let mut graph = Graph::new();
+	for _ in 0..num_nodes {
+		graph.add_node(0.0, 0.0);
+	}
+	for node_id in 1..num_nodes {
+		graph.add_arc(node_id - 1, node_id, 1, 50).unwrap();
+	}
+	let (_, finish_times) = graph.dfs_times(0).unwrap();
+	assert_eq!(Some(2 * num_nodes - 1), finish_times[0]);
+	
+	assert!(graph.dfs_times(num_nodes).is_err());
 }
 
 #[test]
-fn test_costs_time() {
-	let mut graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+fn test_topological_sort() {
+	let graph = build_graph(&[(0.0, 0.0); 6],
+		&[(5, 2, 1, 50), (5, 0, 1, 50), (4, 0, 1, 50), (4, 1, 1, 50), (2, 3, 1, 50), (3, 1, 1, 50)]);
+	
+	let order = graph.topological_sort().unwrap();
+	assert_eq!(vec![4, 5, 2, 0, 3, 1], order);
+	
+	let mut positions = vec![0; graph.num_nodes()];
+	for (position, &node_id) in order.iter().enumerate() {
+		positions[node_id] = position;
+	}
+	for (tail_node, head_node, _, _) in graph.iter_arcs() {
+		assert!(positions[tail_node] < positions[head_node]);
+	}
+	
+	assert!(Graph::new().topological_sort().unwrap().is_empty());
+}
 
-	assert_eq!("[0->1(30), 0->2(70), 1->2(20), 2->3(50), 3->1(40), 4->3(20)]",
-		format!("{}", graph));
+#[test]
+fn test_topological_sort_cycle() {
+	let graph = build_graph(&[(0.0, 0.0); 4], &[(0, 1, 1, 50), (1, 2, 1, 50), (2, 1, 1, 50), (2, 3, 1, 50)]);
+	match graph.topological_sort() {
+		Err(Error::FormatError { message }) => { assert!(message.contains("cycle")); },
+		_ => { assert!(false); }
+	}
+	
+	let graph = build_graph(&[(0.0, 0.0); 2], &[(0, 0, 1, 50)]);
+	assert!(graph.topological_sort().is_err());
+	
+	let graph = Graph::read_graph_from_file_with_options("graphs/test.zip",
+		GraphBuildOptions { directed: false, ..Default::default() }).unwrap();
+	assert!(graph.topological_sort().is_err());
+}
 
-	graph.set_arc_costs_to_travel_time(100);
+#[test]
+fn test_read_cost_model() {
+	let graph = Graph::read_graph_from_file("graphs/test.zip").unwrap();
+	assert_eq!(graph.adjacency_lists[0][0].distance as f64, graph.adjacency_lists[0][0].costs());
+	
+	let options = GraphBuildOptions { cost_model: CostModel::TravelTime(30), ..Default::default() };
+	let graph = Graph::read_graph_from_file_with_options("graphs/test.zip", options).unwrap();
+	for arcs in graph.adjacency_lists.iter() {
+		for arc in arcs.iter() {
+			assert_eq!(arc.distance as f64 * 3.6 / min(arc.max_speed, 30) as f64, arc.costs());
+		}
+	}
+	
+	let data = "2\n1\n0 48.0 7.8\n1 48.1 7.8\n0 1 1000 50\n";
+	let options = GraphBuildOptions { cost_model: CostModel::TravelTime(130), ..Default::default() };
+	let graph = Graph::read_graph_from_reader_with_options(Cursor::new(data), options).unwrap();
+	assert_eq!(72.0, graph.adjacency_lists[0][0].costs());
+}
 
-	assert_eq!("[0->1(3.6), 0->2(8.4), 1->2(2.4), 2->3(6), 3->1(4.8), 4->3(2.4)]",
-		format!("{}", graph));
+#[test]
+fn test_from_str_data() {
+	let graph = Graph::from_str_data("3\n2\n0 48.0 7.8\n1 48.1 7.8\n2 48.2 7.8\n0 1 100 50\n1 2 200 30\n").unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(2, graph.num_arcs());
+	assert_eq!(200, graph.adjacency_lists[1][0].distance());
+	
+	assert!(Graph::from_str_data("3\n2\n0 48.0 7.8\n").is_err());
+}
+
+#[test]
+fn test_to_adjacency_matrix() {
+	let graph = build_undirected_graph(&[(48.0, 7.8); 3],
+		&[(0, 1, 100, 50), (1, 2, 200, 50), (1, 2, 150, 50)]);
+	
+	let matrix = graph.to_adjacency_matrix(3).unwrap();
+	assert_eq!(vec![Some(0.0), Some(100.0), None], matrix[0]);
+	assert_eq!(Some(150.0), matrix[1][2]);
+	for i in 0 .. 3 {
+		for j in 0 .. 3 {
+			assert_eq!(matrix[i][j], matrix[j][i]);
+		}
+	}
+	
+	assert!(graph.to_adjacency_matrix(2).is_err());
+}
+
+#[test]
+fn test_transitive_closure() {
+	let mut graph = Graph::new();
+	for _ in 0 .. 4 {
+		graph.add_node(48.0, 7.8);
+	}
+	graph.add_arc(0, 1, 100, 50).unwrap();
+	graph.add_arc(2, 3, 100, 50).unwrap();
+	graph.add_arc(3, 2, 100, 50).unwrap();
+	
+	let closure = graph.transitive_closure(4).unwrap();
+	assert_eq!(vec![true, true, false, false], closure[0]);
+	assert_eq!(vec![false, true, false, false], closure[1]);
+	assert_eq!(vec![false, false, true, true], closure[2]);
+	assert_eq!(vec![false, false, true, true], closure[3]);
+	
+	assert!(graph.transitive_closure(3).is_err());
+}
+
+#[test]
+fn test_merge() {
+	let tile = |latitude: f64, distance: u64| build_undirected_graph(
+		&[(latitude, 7.8), (latitude + 0.1, 7.8)], &[(0, 1, distance, 50)]);
+	
+	let graph = Graph::merge(tile(48.0, 100), tile(48.1, 200), &[(1, 0)]).unwrap();
+	assert_eq!(3, graph.num_nodes());
+	assert_eq!(4, graph.num_arcs());
+	assert_eq!(vec![0, 2], graph.adjacency_lists[1].iter().map(|arc| arc.head_node_id).collect::<Vec<_>>());
+	assert_eq!(2, graph.adjacency_lists[2][0].tail_node_id);
+	assert_eq!(1, graph.adjacency_lists[2][0].head_node_id);
+	assert_eq!(200, graph.adjacency_lists[2][0].distance);
+	assert_eq!(vec![0, 1, 2], graph.bfs(0).collect::<Vec<_>>());
+	
+	assert!(Graph::merge(tile(48.0, 100), tile(48.1, 200), &[(2, 0)]).is_err());
+	assert!(Graph::merge(tile(48.0, 100), Graph::new(), &[]).is_err());
+	
+	// The file ids of the sparse graph are kept, the other graph uses its indices
+	let data = "2\n1\n30 48.0 7.8\n10 48.1 7.8\n30 10 100 50\n";
+	let options = GraphBuildOptions { directed: false, sparse_ids: true, ..Default::default() };
+	let sparse = Graph::read_graph_from_reader_with_options(Cursor::new(data), options).unwrap();
+	let graph = Graph::merge(sparse, tile(48.1, 200), &[(1, 0)]).unwrap();
+	assert_eq!(vec![Some(30), Some(10), Some(1), None],
+		(0..4).map(|node_id| graph.original_id(node_id)).collect::<Vec<_>>());
+	
+	let data = "2\n1\n30 48.2 7.8\n10 48.3 7.8\n30 10 100 50\n";
+	let options = GraphBuildOptions { directed: false, sparse_ids: true, ..Default::default() };
+	let sparse = Graph::read_graph_from_reader_with_options(Cursor::new(data), options).unwrap();
+	let graph = Graph::merge(tile(48.0, 100), sparse, &[]).unwrap();
+	assert_eq!(vec![Some(0), Some(1), Some(30), Some(10)],
+		(0..4).map(|node_id| graph.original_id(node_id)).collect::<Vec<_>>());
 }
\ No newline at end of file
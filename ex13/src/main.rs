@@ -88,6 +88,17 @@ fn calculate_lcc(graph: &Graph) {
 	let lcc = graph.compute_lcc();
 	let time = (get_time() - start_time).num_milliseconds();
 	println!("Calculated lcc with size {} in {} ms!", &lcc.0, time);
+
+	// Same computation on the compressed sparse row format
+	let start_time = get_time();
+	let csr = graph.to_csr();
+	let conversion_time = (get_time() - start_time).num_milliseconds();
+
+	let start_time = get_time();
+	let lcc = csr.compute_lcc();
+	let time = (get_time() - start_time).num_milliseconds();
+	println!("Calculated lcc with size {} on the csr graph in {} ms (+{} ms conversion)!",
+		&lcc.0, time, conversion_time);
 }
 
 fn calculate_path(graph: &mut Graph, max_speed: u64) -> (String, String) {